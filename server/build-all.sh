@@ -14,7 +14,8 @@ build_image() {
     local tag=$2
     
     echo -e "${BLUE}Building $lang image...${NC}"
-    docker build -t $tag -f dockerfiles/$lang/Dockerfile dockerfiles/$lang/
+    # 构建上下文为 dockerfiles/，各镜像共用 launcher/ 中的启动器源码
+    docker build -t $tag -f dockerfiles/$lang/Dockerfile dockerfiles/
     echo -e "${GREEN}✅ $lang image built successfully${NC}\n"
 }
 
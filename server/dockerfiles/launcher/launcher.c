@@ -0,0 +1,204 @@
+// run_code_launcher：在容器内启动用户程序，从进程外测量并限制资源
+//
+// 用法：run_code_launcher -o <报告文件> [-t 墙钟秒数] [-c CPU 秒数] [-m 地址空间字节数] [-u uid] -- <命令> [参数...]
+//
+// 程序结束后向报告文件写入一行 `墙钟 用户态 内核态 最大常驻内存`，
+// 时间单位为秒（保留 3 位小数），内存单位为 KB，均由 RUSAGE_CHILDREN 得到，包含全部已回收的后代进程。
+//
+// 退出码与 bash 的约定一致：墙钟超时为 124，被信号终止为 128 + 信号值，否则为命令的退出码；
+// CPU 时间超过 -c 后内核先发送 SIGXCPU，1 秒后仍未退出则发送 SIGKILL。
+// 启动器自身出错时退出码为 125，命令无法执行时为 127。
+//
+// 启动器是 child subreaper：用户程序的后代即使 setsid 脱离进程组、清空环境变量后再成为孤儿，
+// 也会被重新挂到启动器下，命令结束或超时后由启动器连同它们一起杀掉，CPU 时间与内存峰值也计入报告。
+//
+// 指定 -u 时命令以该 uid 与同号 gid 运行并清空附加组，启动器自身仍以 root 运行：
+// 用户程序无法写入 root 所有的编译缓存与运行目录，也无法向启动器发送信号。
+// 重定向由调用方的 shell 以 root 打开后继承，报告文件由启动器写入。
+#define _GNU_SOURCE
+#include <dirent.h>
+#include <errno.h>
+#include <grp.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/prctl.h>
+#include <sys/resource.h>
+#include <sys/time.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+static pid_t child;
+static volatile sig_atomic_t timed_out;
+
+static void on_alarm(int sig) {
+    (void)sig;
+    timed_out = 1;
+    kill(-child, SIGKILL);
+}
+
+static double seconds(struct timeval tv) {
+    return tv.tv_sec + tv.tv_usec / 1e6;
+}
+
+// 读取 /proc/<pid>/stat 中的父进程号；进程名可能含空格和括号，从最后一个 ')' 之后解析
+static pid_t parent_of(pid_t pid) {
+    char path[64], buf[512];
+    snprintf(path, sizeof(path), "/proc/%d/stat", (int)pid);
+    FILE *f = fopen(path, "r");
+    if (!f) {
+        return 0;
+    }
+    size_t n = fread(buf, 1, sizeof(buf) - 1, f);
+    fclose(f);
+    buf[n] = '\0';
+    char *end = strrchr(buf, ')');
+    int ppid = 0;
+    if (!end || sscanf(end + 1, " %*c %d", &ppid) != 1) {
+        return 0;
+    }
+    return ppid;
+}
+
+static int is_descendant(pid_t pid, pid_t ancestor) {
+    // 限制层数，避免扫描期间进程号复用造成死循环
+    for (int depth = 0; depth < 4096 && pid > 1; depth++) {
+        pid = parent_of(pid);
+        if (pid == ancestor) {
+            return 1;
+        }
+    }
+    return 0;
+}
+
+// 杀掉并回收全部后代，直到启动器没有任何子进程
+//
+// 扫描与 kill 之间新 fork 出的进程，在父进程被杀后会成为孤儿并挂到启动器下，下一轮扫描时处理。
+static void kill_descendants(void) {
+    pid_t self = getpid();
+    for (;;) {
+        pid_t reaped;
+        while ((reaped = waitpid(-1, NULL, WNOHANG)) > 0) {
+        }
+        if (reaped < 0 && errno == ECHILD) {
+            return;
+        }
+        DIR *proc = opendir("/proc");
+        if (!proc) {
+            return;
+        }
+        struct dirent *entry;
+        while ((entry = readdir(proc))) {
+            pid_t pid = (pid_t)atoi(entry->d_name);
+            if (pid > 1 && pid != self && is_descendant(pid, self)) {
+                kill(pid, SIGKILL);
+            }
+        }
+        closedir(proc);
+        usleep(1000);
+    }
+}
+
+static int usage(void) {
+    fprintf(stderr, "usage: run_code_launcher -o REPORT [-t WALL] [-c CPU] [-m BYTES] [-u UID] -- CMD [ARGS...]\n");
+    return 125;
+}
+
+int main(int argc, char **argv) {
+    const char *report = NULL;
+    double wall_limit = 0, cpu_limit = 0;
+    long long memory_limit = 0;
+    long uid = -1;
+    int opt;
+
+    while ((opt = getopt(argc, argv, "+o:t:c:m:u:")) != -1) {
+        switch (opt) {
+        case 'o': report = optarg; break;
+        case 't': wall_limit = atof(optarg); break;
+        case 'c': cpu_limit = atof(optarg); break;
+        case 'm': memory_limit = atoll(optarg); break;
+        case 'u': uid = atol(optarg); break;
+        default: return usage();
+        }
+    }
+    if (!report || optind >= argc) {
+        return usage();
+    }
+
+    if (prctl(PR_SET_CHILD_SUBREAPER, 1) < 0) {
+        perror("run_code_launcher: prctl");
+        return 125;
+    }
+
+    struct timespec start, end;
+    clock_gettime(CLOCK_MONOTONIC, &start);
+
+    child = fork();
+    if (child < 0) {
+        perror("run_code_launcher: fork");
+        return 125;
+    }
+    if (child == 0) {
+        // 单独的进程组，超时后连同后台子进程一起杀掉
+        setpgid(0, 0);
+        if (cpu_limit > 0) {
+            // RLIMIT_CPU 以整秒计，向上取整；精确的判定由服务端按测得的 CPU 时间完成
+            rlim_t soft = (rlim_t)cpu_limit + (cpu_limit > (rlim_t)cpu_limit);
+            struct rlimit rl = {soft, soft + 1};
+            setrlimit(RLIMIT_CPU, &rl);
+        }
+        if (memory_limit > 0) {
+            struct rlimit rl = {(rlim_t)memory_limit, (rlim_t)memory_limit};
+            setrlimit(RLIMIT_AS, &rl);
+        }
+        // 先清空附加组并切换 gid，切换 uid 后就没有权限再改
+        if (uid >= 0 && (setgroups(0, NULL) < 0 || setgid((gid_t)uid) < 0 || setuid((uid_t)uid) < 0)) {
+            perror("run_code_launcher: setuid");
+            _exit(125);
+        }
+        execvp(argv[optind], argv + optind);
+        perror("run_code_launcher: exec");
+        _exit(127);
+    }
+    setpgid(child, child);
+
+    if (wall_limit > 0) {
+        signal(SIGALRM, on_alarm);
+        struct itimerval timer = {{0, 0}, {(time_t)wall_limit, (suseconds_t)((wall_limit - (time_t)wall_limit) * 1e6)}};
+        setitimer(ITIMER_REAL, &timer, NULL);
+    }
+
+    int status;
+    while (waitpid(child, &status, 0) < 0) {
+        if (errno != EINTR) {
+            perror("run_code_launcher: waitpid");
+            return 125;
+        }
+    }
+    clock_gettime(CLOCK_MONOTONIC, &end);
+    // 不让后台进程残留到下一次运行，包括已脱离进程组的
+    kill(-child, SIGKILL);
+    kill_descendants();
+    // 全部后代都已回收，RUSAGE_CHILDREN 包含它们的 CPU 时间与内存峰值
+    struct rusage usage;
+    getrusage(RUSAGE_CHILDREN, &usage);
+
+    double wall = (end.tv_sec - start.tv_sec) + (end.tv_nsec - start.tv_nsec) / 1e9;
+    FILE *out = fopen(report, "w");
+    if (!out) {
+        perror("run_code_launcher: report");
+        return 125;
+    }
+    fprintf(out, "%.3f %.3f %.3f %ld\n", wall, seconds(usage.ru_utime), seconds(usage.ru_stime), usage.ru_maxrss);
+    fclose(out);
+
+    if (timed_out) {
+        return 124;
+    }
+    if (WIFSIGNALED(status)) {
+        return 128 + WTERMSIG(status);
+    }
+    return WEXITSTATUS(status);
+}
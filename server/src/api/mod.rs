@@ -1 +1,11 @@
+pub mod pool;
 pub mod run_code;
+
+use crate::config::Config;
+use crate::registry::language_registry::LanguageRegistry;
+
+/// 通过 axum State 在各个路由间共享的应用状态
+pub struct AppState {
+    pub config: Config,
+    pub registry: LanguageRegistry,
+}
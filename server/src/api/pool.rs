@@ -0,0 +1,9 @@
+use axum::Json;
+use serde_json::{Value, json};
+
+use crate::docker::pool;
+
+/// 预热池状态：每个镜像的目标数量、空闲数量与命中统计
+pub async fn pool_stats() -> Json<Value> {
+    Json(json!({ "images": pool::stats() }))
+}
@@ -1,43 +1,48 @@
 use axum::Json;
+use axum::extract::State;
 use serde_json::json;
 use std::sync::Arc;
 
-use crate::codegen::c_generator::CCodeGenerator;
-use crate::codegen::cpp_generator::CppCodeGenerator;
-use crate::codegen::python_generator::PythonCodeGenerator;
-use crate::codegen::CodeGenerator;
+use crate::api::AppState;
+use crate::codegen::HarnessSpec;
 use crate::config::Config;
-use crate::executor::universal_executor::execute_code;
-use crate::model::request::CodeRequest;
+use crate::executor::stdio_executor::{StdioSpec, execute_stdio};
+use crate::executor::universal_executor::{RunOptions, execute_code};
+use crate::model::request::{Checker, CodeRequest, RunMode};
+use crate::registry::language_registry::LanguageEntry;
 
 pub async fn run_code(
-    config: Arc<Config>,
+    State(state): State<Arc<AppState>>,
     Json(req): Json<CodeRequest>,
 ) -> Json<serde_json::Value> {
     println!("\n🚀 Request received for language: {}", req.language);
 
-    // 根据语言选择代码生成器
-    let generator: Box<dyn CodeGenerator> = match req.language.as_str() {
-        "c" => Box::new(CCodeGenerator),
-        "cpp" => Box::new(CppCodeGenerator),
-        "python" => Box::new(PythonCodeGenerator),
-        _ => {
-            return Json(json!({
-                "error": format!("Unsupported language: {}", req.language)
-            }));
-        }
+    // 从注册表中查找语言，未启用的语言不会出现在注册表里
+    let Some(language) = state.registry.get(&req.language) else {
+        return Json(json!({
+            "error": format!("Unsupported language: {}", req.language)
+        }));
     };
 
-    match execute_code(
-        &req.language,
-        &req.code,
-        &req.function,
-        &req.test_cases,
-        &config,
-        generator.as_ref(),
-    )
-    .await
-    {
+    // 特判程序可以使用任意已启用的语言
+    let checker = match &req.checker {
+        Some(checker) => match state.registry.get(&checker.language) {
+            Some(entry) => Some((entry, checker)),
+            None => {
+                return Json(json!({
+                    "error": format!("Unsupported checker language: {}", checker.language)
+                }));
+            }
+        },
+        None => None,
+    };
+
+    let result = match req.mode {
+        RunMode::Function => run_function(&req, language, checker, &state.config).await,
+        RunMode::Stdio => run_stdio(&req, language, checker, &state.config).await,
+    };
+
+    match result {
         Ok(result) => {
             println!("✅ Success\n");
             Json(result)
@@ -47,4 +52,73 @@ pub async fn run_code(
             Json(json!({ "error": e.to_string() }))
         }
     }
-}
\ No newline at end of file
+}
+
+async fn run_function(
+    req: &CodeRequest,
+    language: &LanguageEntry,
+    checker: Option<(&LanguageEntry, &Checker)>,
+    config: &Config,
+) -> anyhow::Result<serde_json::Value> {
+    let test_cases = req.function_cases()?;
+    if req.batch_size == Some(0) {
+        anyhow::bail!("batch_size 必须大于 0");
+    }
+    let signature = req.resolved_signature();
+    let spec = HarnessSpec {
+        code: &req.code,
+        function: &req.function,
+        signature: &signature,
+        comparison: &req.comparison,
+        test_cases: &test_cases,
+        benchmark: req.benchmark.as_ref(),
+    };
+
+    execute_code(
+        &req.language,
+        language,
+        &spec,
+        &run_options(req)?,
+        checker,
+        config,
+    )
+    .await
+}
+
+async fn run_stdio(
+    req: &CodeRequest,
+    language: &LanguageEntry,
+    checker: Option<(&LanguageEntry, &Checker)>,
+    config: &Config,
+) -> anyhow::Result<serde_json::Value> {
+    let test_cases = req.stdio_cases()?;
+    if req.complexity.is_some() || req.benchmark.is_some() {
+        anyhow::bail!("复杂度分析与基准测试仅支持函数模式");
+    }
+    let spec = StdioSpec {
+        code: &req.code,
+        cases: &test_cases,
+        whitespace: req.whitespace,
+    };
+
+    execute_stdio(
+        &req.language,
+        language,
+        &spec,
+        &run_options(req)?,
+        checker,
+        config,
+    )
+    .await
+}
+
+fn run_options(req: &CodeRequest) -> anyhow::Result<RunOptions> {
+    if req.memory_limit_mb == Some(0) {
+        anyhow::bail!("memory_limit_mb 必须大于 0");
+    }
+    Ok(RunOptions {
+        batch_size: req.batch_size,
+        memory_limit_mb: req.memory_limit_mb,
+        complexity: req.complexity,
+    })
+}
@@ -1,59 +1,1090 @@
-use crate::codegen::CodeGenerator;
-use crate::model::request::TestCase;
+use crate::codegen::{
+    BENCH_MAX_SAMPLES, BENCH_TARGET_SAMPLES, C_NONCE_READER, CodeGenerator, HarnessSpec,
+    bench_max_batch, c_string_literal, ensure_bench_args_copyable,
+};
+use crate::model::comparison::Comparison;
+use crate::model::request::BenchmarkOptions;
+use crate::model::signature::ValueType;
 use anyhow::Result;
+use serde_json::Value;
 
+/// C 采用 LeetCode 约定：数组参数展开为 (指针, 长度)，二维数组额外传每行长度，
+/// 返回数组时通过 returnSize / returnColumnSizes 指针带回长度；
+/// 字符串参数展开为 (const char*, 字节长度)
 pub struct CCodeGenerator;
 
 impl CodeGenerator for CCodeGenerator {
-    fn generate(&self, code: &str, function: &str, test_cases: &[TestCase]) -> Result<String> {
-        let mut full = String::with_capacity(code.len() + test_cases.len() * 250 + 200);
+    fn generate(&self, spec: &HarnessSpec) -> Result<String> {
+        let signature = spec.signature;
+        for ty in signature.params.iter().chain([&signature.returns]) {
+            if ty.depth() > 2 {
+                anyhow::bail!("C 最多支持二维数组，不支持 {}", ty);
+            }
+        }
+        ensure_bench_args_copyable(spec, "C")?;
+        let comparison = spec.comparison;
+        if matches!(comparison, Comparison::Unordered | Comparison::Set)
+            && signature.returns.depth() != 1
+        {
+            anyhow::bail!("C 的 {} 比较仅支持一维数组返回值", comparison.name());
+        }
+
+        let mut full = String::with_capacity(spec.code.len() + spec.test_cases.len() * 500 + 600);
 
         full.push_str("#include <stdio.h>\n");
+        full.push_str("#include <stdbool.h>\n");
+        full.push_str("#include <string.h>\n");
         full.push_str("#include <time.h>\n");
-        full.push_str("#include <sys/resource.h>\n\n");
-        full.push_str(code);
-        full.push_str("\n\nlong get_memory_kb() {\n");
-        full.push_str("    struct rusage usage;\n");
-        full.push_str("    getrusage(RUSAGE_SELF, &usage);\n");
-        full.push_str("    return usage.ru_maxrss;\n");
+        match comparison {
+            Comparison::Unordered | Comparison::Set => full.push_str("#include <stdlib.h>\n"),
+            Comparison::CaseInsensitive => full.push_str("#include <strings.h>\n"),
+            _ => {}
+        }
+        // 基准测试为参数副本分配内存，并统计堆的增长
+        if spec.benchmark.is_some() {
+            full.push_str("#include <stdlib.h>\n");
+            full.push_str("#include <malloc.h>\n");
+        }
+        full.push('\n');
+        // 结构体定义需在用户代码之前
+        if signature.uses(&ValueType::List) {
+            full.push_str(LIST_PRELUDE);
+        }
+        if signature.uses(&ValueType::Tree) {
+            full.push_str(TREE_PRELUDE);
+        }
+        full.push_str(spec.code);
+        full.push_str("\n\n");
+        full.push_str(C_NONCE_READER);
+        // 与其它语言一致使用单调时钟的墙钟时间，clock() 给出的是 CPU 时间
+        full.push_str("static double harness_now_ms(void) {\n");
+        full.push_str("    struct timespec ts;\n");
+        full.push_str("    clock_gettime(CLOCK_MONOTONIC, &ts);\n");
+        full.push_str("    return ts.tv_sec * 1000.0 + ts.tv_nsec / 1e6;\n");
         full.push_str("}\n\n");
 
-        full.push_str("int main() {\n");
-        full.push_str("    clock_t program_start = clock();\n");
-        full.push_str("    long start_mem = get_memory_kb();\n\n");
-
-        for t in test_cases {
-            let args = t
-                .input
-                .iter()
-                .map(|x| x.to_string())
-                .collect::<Vec<_>>()
-                .join(", ");
-            full.push_str("    {\n");
-            full.push_str("        clock_t start = clock();\n");
-            full.push_str(&format!("        int result = {}({});\n", function, args));
-            full.push_str("        clock_t end = clock();\n");
-            full.push_str(
-                "        double time_ms = (double)(end - start) / CLOCKS_PER_SEC * 1000.0;\n",
-            );
+        if *signature.returns.scalar() == ValueType::String {
+            full.push_str("void print_json_string(FILE* out, const char* s) {\n");
+            full.push_str("    if (!s) { fprintf(out, \"null\"); return; }\n");
+            full.push_str("    fputc('\"', out);\n");
+            full.push_str("    for (; *s; s++) {\n");
+            full.push_str("        unsigned char c = (unsigned char)*s;\n");
+            full.push_str("        if (c == '\"' || c == '\\\\') fprintf(out, \"\\\\%c\", c);\n");
+            full.push_str("        else if (c == '\\n') fprintf(out, \"\\\\n\");\n");
+            full.push_str("        else if (c < 0x20) fprintf(out, \"\\\\u%04x\", c);\n");
+            full.push_str("        else fputc(c, out);\n");
+            full.push_str("    }\n");
+            full.push_str("    fputc('\"', out);\n");
+            full.push_str("}\n\n");
+        }
+
+        // nan / inf 不是合法的 JSON，输出为字符串
+        if *signature.returns.scalar() == ValueType::Double {
+            full.push_str("void print_double(FILE* out, double v) {\n");
+            full.push_str("    if (v != v) fprintf(out, \"\\\"NaN\\\"\");\n");
+            full.push_str("    else if (v - v != 0) fprintf(out, v > 0 ? \"\\\"Infinity\\\"\" : \"\\\"-Infinity\\\"\");\n");
+            full.push_str("    else fprintf(out, \"%.15g\", v);\n");
+            full.push_str("}\n\n");
+        }
+
+        push_checker(&mut full, comparison, signature.returns.scalar());
+        // 各用例共用，放在栈外避免用例多时栈溢出
+        if spec.benchmark.is_some() {
+            full.push_str(&format!(
+                "static double bench_samples[{}];\n\n",
+                BENCH_MAX_SAMPLES
+            ));
+            full.push_str(BENCH_HEAP_BYTES);
+        }
+
+        full.push_str("int main(int argc, char** argv) {\n");
+        push_open_results(&mut full);
+        full.push_str(&format!(
+            "    int harness_first = 0, harness_last = {};\n",
+            spec.test_cases.len()
+        ));
+        full.push_str("    if (argc > 2) {\n");
+        full.push_str("        sscanf(argv[1], \"%d\", &harness_first);\n");
+        full.push_str("        sscanf(argv[2], \"%d\", &harness_last);\n");
+        full.push_str("    }\n");
+        full.push_str("    double program_start = harness_now_ms();\n\n");
+
+        for (index, t) in spec.test_cases.iter().enumerate() {
+            let returns = &signature.returns;
+            let mut args = Vec::new();
+            let mut bench = BenchArgs::default();
+
+            full.push_str(&format!(
+                "    if (harness_first <= {0} && {0} < harness_last) {{\n",
+                index
+            ));
+            let expected = returns.canonical(&t.expected);
+            for (j, (ty, value)) in signature.params.iter().zip(&t.input).enumerate() {
+                let name = format!("arg{}", j);
+                let declared = if ty.is_node() {
+                    vec![declare_node(&mut full, &name, ty, value)]
+                } else if *ty == ValueType::String {
+                    let s = value.as_str().unwrap_or_default();
+                    vec![c_string_literal(s), s.len().to_string()]
+                } else if ty.is_scalar() {
+                    vec![scalar_literal(ty, value)]
+                } else {
+                    // 数组参数在基准测试中改用副本
+                    args.extend(declare_array(&mut full, &name, ty, value));
+                    if spec.benchmark.is_some() {
+                        bench.push_array(&mut full, &name, ty, value);
+                    }
+                    continue;
+                };
+                bench.args.extend(declared.iter().cloned());
+                args.extend(declared);
+            }
+            let expected_sizes = if returns.is_node() {
+                declare_node(&mut full, "expected", returns, &expected);
+                Vec::new()
+            } else if returns.is_scalar() {
+                full.push_str(&format!(
+                    "        {} expected = {};\n",
+                    c_type(returns),
+                    scalar_literal(returns, &expected)
+                ));
+                Vec::new()
+            } else {
+                declare_array(&mut full, "expected", returns, &expected)
+            };
+
+            match returns.depth() {
+                0 => {}
+                1 => {
+                    full.push_str("        int result_size = 0;\n");
+                    args.push("&result_size".to_string());
+                }
+                _ => {
+                    full.push_str("        int result_size = 0;\n");
+                    full.push_str("        int* result_cols = NULL;\n");
+                    args.push("&result_size".to_string());
+                    args.push("&result_cols".to_string());
+                }
+            }
+
+            full.push_str("        double start = harness_now_ms();\n");
+            full.push_str(&format!(
+                "        {} result = {}({});\n",
+                c_type(returns),
+                spec.function,
+                args.join(", ")
+            ));
+            full.push_str("        double time_ms = harness_now_ms() - start;\n");
+            push_compare(&mut full, returns, comparison, &expected_sizes);
+            if let Some(benchmark) = spec.benchmark {
+                // 基准测试的调用使用单独的长度变量，不覆盖要输出的第一次结果
+                bench.args.extend(
+                    ["&bench_size", "&bench_cols"]
+                        .iter()
+                        .take(returns.depth())
+                        .map(|arg| arg.to_string()),
+                );
+                push_bench(&mut full, benchmark, returns, spec.function, &bench);
+            }
             full.push_str(&format!(
-                "        printf(\"Input: ({}) => Output: %d | Expected: {} | %s | Time: %.3fms\\n\", \
-                 result, result == {} ? \"✅ PASS\" : \"❌ FAIL\", time_ms);\n",
-                args, t.expected, t.expected
+                "        fprintf(harness_out, \"{{\\\"nonce\\\":\\\"%s\\\",\\\"index\\\":{},\\\"passed\\\":%s,\\\"time_ms\\\":%.3f,\\\"output\\\":\", harness_nonce, passed ? \"true\" : \"false\", time_ms);\n",
+                index
             ));
+            push_print(&mut full, returns);
+            if spec.benchmark.is_some() {
+                full.push_str("        fprintf(harness_out, \",\\\"bench\\\":{\\\"iterations\\\":%lld,\\\"samples\\\":[\", bench_iterations);\n");
+                full.push_str("        for (int k = 0; k < bench_count; k++) fprintf(harness_out, k ? \",%.9g\" : \"%.9g\", bench_samples[k]);\n");
+                full.push_str("        fprintf(harness_out, \"]}\");\n");
+            }
+            full.push_str("        fprintf(harness_out, \"}\\n\");\n");
+            full.push_str("        fflush(harness_out);\n");
             full.push_str("    }\n\n");
         }
 
-        full.push_str("    clock_t program_end = clock();\n");
-        full.push_str("    long end_mem = get_memory_kb();\n");
-        full.push_str("    double total_time = (double)(program_end - program_start) / CLOCKS_PER_SEC * 1000.0;\n");
-        full.push_str("    printf(\"\\n=== Performance ===\\n\");\n");
-        full.push_str("    printf(\"Time: %.3fms\\n\", total_time);\n");
-        full.push_str("    printf(\"Memory: %ld KB\\n\", end_mem - start_mem);\n");
+        full.push_str("    double total_time = harness_now_ms() - program_start;\n");
+        full.push_str("    fprintf(harness_out, \"{\\\"nonce\\\":\\\"%s\\\",\\\"done\\\":true,\\\"time_ms\\\":%.3f}\\n\", harness_nonce, total_time);\n");
+        full.push_str("    fclose(harness_out);\n");
 
         full.push_str("    return 0;\n");
         full.push('}');
 
         Ok(full)
     }
-}
\ No newline at end of file
+}
+
+/// 基准测试中堆最多增长的字节数，超出后提前结束测量
+const BENCH_LEAK_BYTES: u64 = 32 * 1024 * 1024;
+
+/// 堆上已分配的字节数，基准测试据此限制返回值泄漏的内存
+const BENCH_HEAP_BYTES: &str = r#"static double bench_heap_bytes(void) {
+    struct mallinfo2 info = mallinfo2();
+    return (double)(info.uordblks + info.hblkhd);
+}
+
+"#;
+
+/// 基准测试每次调用的实参：非空数组参数每次调用使用单独的副本，
+/// 副本在计时之外从另外声明的原始字面量复制，函数改写参数不影响之后的调用
+#[derive(Default)]
+struct BenchArgs {
+    /// 实参表达式，k 为本批中调用的序号
+    args: Vec<String>,
+    /// 为 bench_max_batch 份副本分配内存
+    alloc: String,
+    /// 准备第 k 份副本
+    fill: String,
+    free: String,
+    /// 需要复制的参数值
+    copied: Vec<Value>,
+}
+
+impl BenchArgs {
+    fn push_array(&mut self, out: &mut String, name: &str, ty: &ValueType, value: &Value) {
+        let items = value.as_array().map(Vec::as_slice).unwrap_or_default();
+        let ValueType::Array(inner) = ty else {
+            unreachable!()
+        };
+        let copy = format!("bench_{}", name);
+        let src = format!("{}_src", copy);
+        let declared = declare_array(out, &src, ty, value);
+        // 空数组为 NULL，无需复制
+        if items.is_empty() {
+            self.args.extend(declared);
+            return;
+        }
+        self.copied.push(value.clone());
+
+        let element = c_type(inner.scalar());
+        let n = items.len();
+        if inner.is_scalar() {
+            self.alloc.push_str(&format!(
+                "        {} {} = malloc(sizeof({}) * bench_max_batch);\n",
+                c_type(ty),
+                copy,
+                src
+            ));
+            self.fill.push_str(&format!(
+                " memcpy({0} + k * {1}, {2}, sizeof({2}));",
+                copy, n, src
+            ));
+            self.args.push(format!("{} + k * {}", copy, n));
+            self.args.push(n.to_string());
+            self.free.push_str(&format!(" free({});", copy));
+            return;
+        }
+
+        // 二维数组：每份副本有自己的行指针、每行长度与行数据
+        let total: usize = items
+            .iter()
+            .map(|row| row.as_array().map_or(0, Vec::len))
+            .sum();
+        self.alloc.push_str(&format!(
+            "        {0}** {1} = malloc(sizeof({0}*) * {2} * bench_max_batch);\n",
+            element, copy, n
+        ));
+        self.alloc.push_str(&format!(
+            "        int* {0}_cols = malloc(sizeof({1}_cols) * bench_max_batch);\n",
+            copy, src
+        ));
+        self.alloc.push_str(&format!(
+            "        {0}* {1}_data = malloc(sizeof({0}) * {2} * bench_max_batch);\n",
+            element,
+            copy,
+            total.max(1)
+        ));
+        self.fill.push_str(&format!(
+            " {{ {0}* d = {1}_data + k * {2}; for (int r = 0; r < {3}; r++) {{ {1}[k * {3} + r] = d; if ({4}_cols[r]) memcpy(d, {4}[r], sizeof({0}) * {4}_cols[r]); d += {4}_cols[r]; }} memcpy({1}_cols + k * {3}, {4}_cols, sizeof({4}_cols)); }}",
+            element, copy, total, n, src
+        ));
+        self.args.push(format!("{} + k * {}", copy, n));
+        self.args.push(n.to_string());
+        self.args.push(format!("{}_cols + k * {}", copy, n));
+        self.free.push_str(&format!(
+            " free({0}); free({0}_cols); free({0}_data);",
+            copy
+        ));
+    }
+}
+
+/// 基准测试：预热时每轮调用次数翻倍，直到用满 warmup_ms，由此估出单次耗时；
+/// 之后按估出的单次耗时分批调用，直到测量时间达到 min_time_ms，每批的平均耗时为一个样本
+///
+/// 每批调用前在计时之外准备好参数副本，每批的调用次数受副本大小限制。
+/// 返回的内存可能指向参数、常量或静态缓冲区，无法确定来自 malloc，因此不释放；
+/// 堆的增长超过 BENCH_LEAK_BYTES 后提前结束，每批的调用次数也按预热中每次调用的增长量限制。
+fn push_bench(
+    out: &mut String,
+    benchmark: &BenchmarkOptions,
+    returns: &ValueType,
+    function: &str,
+    bench: &BenchArgs,
+) {
+    out.push_str(&format!(
+        "        long long bench_max_batch = {}LL;\n",
+        bench_max_batch(&bench.copied)
+    ));
+    out.push_str(&bench.alloc);
+    // volatile 防止编译器把结果未被使用的调用优化掉
+    out.push_str(&format!(
+        "        {} volatile bench_result = 0;\n",
+        c_type(returns)
+    ));
+    match returns.depth() {
+        0 => {}
+        1 => out.push_str("        int bench_size = 0;\n"),
+        _ => {
+            out.push_str("        int bench_size = 0;\n");
+            out.push_str("        int* bench_cols = NULL;\n");
+        }
+    }
+    out.push_str("        int bench_count = 0;\n");
+    out.push_str("        long long bench_iterations = 0, bench_calls = 0, bench_batch = 1;\n");
+    out.push_str("        double bench_elapsed = 0, bench_leaked = 0;\n");
+
+    let mut batch = String::new();
+    if !bench.fill.is_empty() {
+        batch.push_str(&format!(
+            "            for (long long k = 0; k < bench_batch; k++) {{{} }}\n",
+            bench.fill
+        ));
+    }
+    batch.push_str("            double bench_sample_start = harness_now_ms();\n");
+    batch.push_str(&format!(
+        "            for (long long k = 0; k < bench_batch; k++) bench_result = {}({});\n",
+        function,
+        bench.args.join(", ")
+    ));
+    batch.push_str("            double bench_sample_ms = harness_now_ms() - bench_sample_start;\n");
+    batch.push_str("            bench_leaked = bench_heap_bytes() - bench_heap_start;\n");
+
+    // 副本在此之前分配，不计入增长
+    out.push_str("        double bench_heap_start = bench_heap_bytes();\n");
+    out.push_str("        double bench_start = harness_now_ms();\n");
+    out.push_str("        do {\n");
+    out.push_str(&batch);
+    out.push_str("            bench_elapsed += bench_sample_ms;\n");
+    out.push_str("            bench_calls += bench_batch;\n");
+    out.push_str("            if (bench_batch * 2 <= bench_max_batch) bench_batch *= 2;\n");
+    out.push_str(&format!(
+        "        }} while (harness_now_ms() - bench_start < {:?} && bench_leaked < {});\n",
+        benchmark.warmup_ms, BENCH_LEAK_BYTES
+    ));
+    out.push_str("        double bench_per_call = bench_elapsed / bench_calls;\n");
+    out.push_str("        if (bench_per_call < 1e-6) bench_per_call = 1e-6;\n");
+    out.push_str(&format!(
+        "        if (bench_leaked > 0 && bench_max_batch > {0} / (bench_leaked / bench_calls)) bench_max_batch = (long long)({0} / (bench_leaked / bench_calls));\n",
+        BENCH_LEAK_BYTES
+    ));
+    out.push_str(&format!(
+        "        bench_batch = (long long)({:?} / {} / bench_per_call);\n",
+        benchmark.min_time_ms, BENCH_TARGET_SAMPLES
+    ));
+    out.push_str("        if (bench_batch > bench_max_batch) bench_batch = bench_max_batch;\n");
+    out.push_str("        if (bench_batch < 1) bench_batch = 1;\n");
+    out.push_str("        bench_start = harness_now_ms();\n");
+    out.push_str(&format!(
+        "        while (bench_count < {} && (bench_count == 0 || (harness_now_ms() - bench_start < {:?} && bench_leaked < {}))) {{\n",
+        BENCH_MAX_SAMPLES, benchmark.min_time_ms, BENCH_LEAK_BYTES
+    ));
+    out.push_str(&batch);
+    out.push_str("            bench_samples[bench_count++] = bench_sample_ms / bench_batch;\n");
+    out.push_str("            bench_iterations += bench_batch;\n");
+    out.push_str("        }\n");
+    out.push_str("        (void)bench_result;\n");
+    if !bench.free.is_empty() {
+        out.push_str(&format!("       {}\n", bench.free));
+    }
+}
+
+/// 打开结果通道，见 [`crate::codegen::RESULTS_FD`]；nonce 已由 [`C_NONCE_READER`] 读取
+fn push_open_results(out: &mut String) {
+    out.push_str("    FILE* harness_out = fdopen(3, \"w\");\n");
+    out.push_str("    if (!harness_out) {\n");
+    out.push_str("        fprintf(stderr, \"results fd 3 is not open\\n\");\n");
+    out.push_str("        return 1;\n");
+    out.push_str("    }\n");
+}
+
+/// LeetCode 的链表定义，以及构造、输出、比较函数
+const LIST_PRELUDE: &str = r#"#include <stdlib.h>
+
+struct ListNode {
+    int val;
+    struct ListNode *next;
+};
+
+struct ListNode* build_list(const int* vals, int n) {
+    struct ListNode* head = NULL;
+    for (int i = n - 1; i >= 0; i--) {
+        struct ListNode* node = malloc(sizeof(struct ListNode));
+        node->val = vals[i];
+        node->next = head;
+        head = node;
+    }
+    return head;
+}
+
+void print_list(FILE* out, struct ListNode* head) {
+    fprintf(out, "[");
+    for (struct ListNode* p = head; p; p = p->next) {
+        fprintf(out, p == head ? "%d" : ",%d", p->val);
+    }
+    fprintf(out, "]");
+}
+
+int list_equal(struct ListNode* a, struct ListNode* b) {
+    for (; a && b; a = a->next, b = b->next) {
+        if (a->val != b->val) return 0;
+    }
+    return a == NULL && b == NULL;
+}
+
+"#;
+
+/// LeetCode 的二叉树定义，层序数组中的 null 通过 nulls 标记
+const TREE_PRELUDE: &str = r#"#include <stdlib.h>
+
+struct TreeNode {
+    int val;
+    struct TreeNode *left;
+    struct TreeNode *right;
+};
+
+struct TreeNode* build_tree(const int* vals, const bool* nulls, int n) {
+    if (n == 0 || nulls[0]) return NULL;
+    struct TreeNode** nodes = malloc(n * sizeof(struct TreeNode*));
+    for (int i = 0; i < n; i++) {
+        nodes[i] = NULL;
+        if (nulls[i]) continue;
+        nodes[i] = malloc(sizeof(struct TreeNode));
+        nodes[i]->val = vals[i];
+        nodes[i]->left = nodes[i]->right = NULL;
+    }
+    for (int i = 0, child = 1; i < n && child < n; i++) {
+        if (!nodes[i]) continue;
+        nodes[i]->left = nodes[child++];
+        if (child < n) nodes[i]->right = nodes[child++];
+    }
+    struct TreeNode* root = nodes[0];
+    free(nodes);
+    return root;
+}
+
+void print_tree(FILE* out, struct TreeNode* root) {
+    int cap = 16, head = 0, tail = 0;
+    struct TreeNode** queue = malloc(cap * sizeof(struct TreeNode*));
+    if (root) queue[tail++] = root;
+    while (head < tail) {
+        struct TreeNode* node = queue[head++];
+        if (!node) continue;
+        if (tail + 2 > cap) {
+            cap *= 2;
+            queue = realloc(queue, cap * sizeof(struct TreeNode*));
+        }
+        queue[tail++] = node->left;
+        queue[tail++] = node->right;
+    }
+    while (tail > 0 && !queue[tail - 1]) tail--;
+    fprintf(out, "[");
+    for (int i = 0; i < tail; i++) {
+        if (i) fprintf(out, ",");
+        if (queue[i]) fprintf(out, "%d", queue[i]->val);
+        else fprintf(out, "null");
+    }
+    fprintf(out, "]");
+    free(queue);
+}
+
+int tree_equal(struct TreeNode* a, struct TreeNode* b) {
+    if (!a || !b) return a == b;
+    return a->val == b->val && tree_equal(a->left, b->left) && tree_equal(a->right, b->right);
+}
+
+"#;
+
+/// 标量或数组（指针）对应的 C 类型
+fn c_type(ty: &ValueType) -> String {
+    let scalar = match ty.scalar() {
+        ValueType::Int => "int",
+        ValueType::Long => "long long",
+        ValueType::Double => "double",
+        ValueType::Bool => "bool",
+        ValueType::String => "char*",
+        ValueType::List => "struct ListNode*",
+        ValueType::Tree => "struct TreeNode*",
+        ValueType::Array(_) => unreachable!(),
+    };
+    format!("{}{}", scalar, "*".repeat(ty.depth()))
+}
+
+fn scalar_literal(ty: &ValueType, value: &Value) -> String {
+    match ty {
+        ValueType::Long => format!("{}LL", value),
+        ValueType::Double => format!("{:?}", value.as_f64().unwrap_or_default()),
+        ValueType::String => c_string_literal(value.as_str().unwrap_or_default()),
+        _ => value.to_string(),
+    }
+}
+
+/// 声明数组变量，返回传参时需要的实参（数组名与长度）
+fn declare_array(out: &mut String, name: &str, ty: &ValueType, value: &Value) -> Vec<String> {
+    let items = value.as_array().map(Vec::as_slice).unwrap_or_default();
+    let ValueType::Array(inner) = ty else {
+        unreachable!()
+    };
+
+    if inner.is_scalar() {
+        declare_row(out, name, inner, items);
+        return vec![name.to_string(), items.len().to_string()];
+    }
+
+    let element = c_type(inner);
+    let mut cols = Vec::with_capacity(items.len());
+    for (r, row) in items.iter().enumerate() {
+        let row_items = row.as_array().map(Vec::as_slice).unwrap_or_default();
+        declare_row(out, &format!("{}_{}", name, r), inner.scalar(), row_items);
+        cols.push(row_items.len().to_string());
+    }
+
+    if items.is_empty() {
+        out.push_str(&format!("        {}* {} = NULL;\n", element, name));
+        out.push_str(&format!("        int* {}_cols = NULL;\n", name));
+    } else {
+        let rows = (0..items.len())
+            .map(|r| format!("{}_{}", name, r))
+            .collect::<Vec<_>>()
+            .join(", ");
+        out.push_str(&format!("        {} {}[] = {{{}}};\n", element, name, rows));
+        out.push_str(&format!(
+            "        int {}_cols[] = {{{}}};\n",
+            name,
+            cols.join(", ")
+        ));
+    }
+
+    vec![
+        name.to_string(),
+        items.len().to_string(),
+        format!("{}_cols", name),
+    ]
+}
+
+fn declare_row(out: &mut String, name: &str, scalar: &ValueType, items: &[Value]) {
+    // C 不允许空的数组初始化列表，空数组用 NULL 代替
+    if items.is_empty() {
+        out.push_str(&format!("        {}* {} = NULL;\n", c_type(scalar), name));
+    } else {
+        let literals = items
+            .iter()
+            .map(|v| scalar_literal(scalar, v))
+            .collect::<Vec<_>>()
+            .join(", ");
+        out.push_str(&format!(
+            "        {} {}[] = {{{}}};\n",
+            c_type(scalar),
+            name,
+            literals
+        ));
+    }
+}
+
+/// 声明链表或二叉树：先声明层序数组，再调用构造函数，返回变量名
+fn declare_node(out: &mut String, name: &str, ty: &ValueType, value: &Value) -> String {
+    let items = value.as_array().map(Vec::as_slice).unwrap_or_default();
+    let vals = format!("{}_vals", name);
+    declare_row(
+        out,
+        &vals,
+        &ValueType::Int,
+        &items
+            .iter()
+            .map(|v| {
+                if v.is_null() {
+                    Value::from(0)
+                } else {
+                    v.clone()
+                }
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    if *ty == ValueType::List {
+        out.push_str(&format!(
+            "        struct ListNode* {} = build_list({}, {});\n",
+            name,
+            vals,
+            items.len()
+        ));
+    } else {
+        let nulls = items
+            .iter()
+            .map(|v| {
+                if v.is_null() {
+                    Value::Bool(true)
+                } else {
+                    Value::Bool(false)
+                }
+            })
+            .collect::<Vec<_>>();
+        declare_row(out, &format!("{}_nulls", name), &ValueType::Bool, &nulls);
+        out.push_str(&format!(
+            "        struct TreeNode* {} = build_tree({}, {}_nulls, {});\n",
+            name,
+            vals,
+            name,
+            items.len()
+        ));
+    }
+    name.to_string()
+}
+
+/// 非精确比较所需的辅助函数
+fn push_checker(out: &mut String, comparison: &Comparison, scalar: &ValueType) {
+    match comparison {
+        Comparison::Epsilon { abs, rel } => {
+            out.push_str("int approx_equal(double a, double b) {\n");
+            out.push_str("    double diff = a > b ? a - b : b - a;\n");
+            out.push_str("    double scale = b < 0 ? -b : b;\n");
+            out.push_str(&format!(
+                "    return diff <= {:?} || diff <= {:?} * scale;\n",
+                abs, rel
+            ));
+            out.push_str("}\n\n");
+        }
+        // 复制后排序，set 模式再去重，比较排序后的数组
+        Comparison::Unordered | Comparison::Set => {
+            let ty = c_type(scalar);
+            out.push_str("int compare_items(const void* a, const void* b) {\n");
+            out.push_str(&format!("    {} x = *({}*)a;\n", ty, ty));
+            out.push_str(&format!("    {} y = *({}*)b;\n", ty, ty));
+            if *scalar == ValueType::String {
+                out.push_str("    return strcmp(x, y);\n");
+            } else {
+                out.push_str("    return (x > y) - (x < y);\n");
+            }
+            out.push_str("}\n\n");
+            out.push_str(&format!(
+                "int normalize_items({}* items, int n, {}** out) {{\n",
+                ty, ty
+            ));
+            out.push_str(&format!(
+                "    *out = malloc((n > 0 ? n : 1) * sizeof({}));\n",
+                ty
+            ));
+            out.push_str(&format!(
+                "    if (n > 0) memcpy(*out, items, n * sizeof({}));\n",
+                ty
+            ));
+            out.push_str(&format!(
+                "    qsort(*out, n, sizeof({}), compare_items);\n",
+                ty
+            ));
+            if *comparison == Comparison::Set {
+                out.push_str("    int m = 0;\n");
+                out.push_str("    for (int i = 0; i < n; i++) {\n");
+                out.push_str("        if (m == 0 || compare_items(&(*out)[m - 1], &(*out)[i]) != 0) (*out)[m++] = (*out)[i];\n");
+                out.push_str("    }\n");
+                out.push_str("    return m;\n");
+            } else {
+                out.push_str("    return n;\n");
+            }
+            out.push_str("}\n\n");
+        }
+        _ => {}
+    }
+}
+
+fn scalar_eq(ty: &ValueType, comparison: &Comparison, a: &str, b: &str) -> String {
+    match ty {
+        ValueType::Double if matches!(comparison, Comparison::Epsilon { .. }) => {
+            format!("approx_equal({}, {})", a, b)
+        }
+        ValueType::String if *comparison == Comparison::CaseInsensitive => {
+            format!("({} != NULL && strcasecmp({}, {}) == 0)", a, a, b)
+        }
+        ValueType::String => format!("({} != NULL && strcmp({}, {}) == 0)", a, a, b),
+        ValueType::List => format!("list_equal({}, {})", a, b),
+        ValueType::Tree => format!("tree_equal({}, {})", a, b),
+        _ => format!("{} == {}", a, b),
+    }
+}
+
+/// 生成 `int passed = ...` 的比较代码
+fn push_compare(
+    out: &mut String,
+    ty: &ValueType,
+    comparison: &Comparison,
+    expected_sizes: &[String],
+) {
+    let scalar = ty.scalar();
+    if matches!(comparison, Comparison::Unordered | Comparison::Set) {
+        let elem = c_type(scalar);
+        out.push_str(&format!("        {}* sorted_result;\n", elem));
+        out.push_str(&format!("        {}* sorted_expected;\n", elem));
+        out.push_str("        int sorted_result_size = normalize_items(result, result_size, &sorted_result);\n");
+        out.push_str(&format!(
+            "        int sorted_expected_size = normalize_items(expected, {}, &sorted_expected);\n",
+            expected_sizes[1]
+        ));
+        out.push_str("        int passed = sorted_result_size == sorted_expected_size;\n");
+        out.push_str("        for (int i = 0; passed && i < sorted_result_size; i++) {\n");
+        out.push_str(&format!(
+            "            passed = {};\n",
+            scalar_eq(scalar, comparison, "sorted_result[i]", "sorted_expected[i]")
+        ));
+        out.push_str("        }\n");
+        return;
+    }
+    match ty.depth() {
+        0 => out.push_str(&format!(
+            "        int passed = {};\n",
+            scalar_eq(scalar, comparison, "result", "expected")
+        )),
+        1 => {
+            let n = &expected_sizes[1];
+            out.push_str(&format!("        int passed = result_size == {};\n", n));
+            out.push_str(&format!(
+                "        for (int i = 0; passed && i < {}; i++) {{\n",
+                n
+            ));
+            out.push_str(&format!(
+                "            passed = {};\n",
+                scalar_eq(scalar, comparison, "result[i]", "expected[i]")
+            ));
+            out.push_str("        }\n");
+        }
+        _ => {
+            let n = &expected_sizes[1];
+            out.push_str(&format!("        int passed = result_size == {};\n", n));
+            out.push_str(&format!(
+                "        for (int i = 0; passed && i < {}; i++) {{\n",
+                n
+            ));
+            out.push_str("            passed = result_cols[i] == expected_cols[i];\n");
+            out.push_str("            for (int j = 0; passed && j < expected_cols[i]; j++) {\n");
+            out.push_str(&format!(
+                "                passed = {};\n",
+                scalar_eq(scalar, comparison, "result[i][j]", "expected[i][j]")
+            ));
+            out.push_str("            }\n");
+            out.push_str("        }\n");
+        }
+    }
+}
+
+fn print_scalar(ty: &ValueType, expr: &str) -> String {
+    match ty {
+        ValueType::Int => format!("fprintf(harness_out, \"%d\", {});", expr),
+        ValueType::Long => format!("fprintf(harness_out, \"%lld\", {});", expr),
+        ValueType::Double => format!("print_double(harness_out, {});", expr),
+        ValueType::Bool => format!(
+            "fprintf(harness_out, \"%s\", {} ? \"true\" : \"false\");",
+            expr
+        ),
+        ValueType::String => format!("print_json_string(harness_out, {});", expr),
+        ValueType::List => format!("print_list(harness_out, {});", expr),
+        ValueType::Tree => format!("print_tree(harness_out, {});", expr),
+        ValueType::Array(_) => unreachable!(),
+    }
+}
+
+/// 以 JSON 格式输出返回值
+fn push_print(out: &mut String, ty: &ValueType) {
+    let scalar = ty.scalar();
+    match ty.depth() {
+        0 => out.push_str(&format!("        {}\n", print_scalar(scalar, "result"))),
+        1 => {
+            out.push_str("        fprintf(harness_out, \"[\");\n");
+            out.push_str("        for (int i = 0; i < result_size; i++) {\n");
+            out.push_str("            if (i) fprintf(harness_out, \",\");\n");
+            out.push_str(&format!(
+                "            {}\n",
+                print_scalar(scalar, "result[i]")
+            ));
+            out.push_str("        }\n");
+            out.push_str("        fprintf(harness_out, \"]\");\n");
+        }
+        _ => {
+            out.push_str("        fprintf(harness_out, \"[\");\n");
+            out.push_str("        for (int i = 0; i < result_size; i++) {\n");
+            out.push_str("            if (i) fprintf(harness_out, \",\");\n");
+            out.push_str("            fprintf(harness_out, \"[\");\n");
+            out.push_str("            for (int j = 0; j < result_cols[i]; j++) {\n");
+            out.push_str("                if (j) fprintf(harness_out, \",\");\n");
+            out.push_str(&format!(
+                "                {}\n",
+                print_scalar(scalar, "result[i][j]")
+            ));
+            out.push_str("            }\n");
+            out.push_str("            fprintf(harness_out, \"]\");\n");
+            out.push_str("        }\n");
+            out.push_str("        fprintf(harness_out, \"]\");\n");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::request::TestCase;
+    use crate::model::signature::Signature;
+    use serde_json::json;
+
+    fn generate(signature: Signature, test_cases: &[TestCase]) -> String {
+        generate_with(signature, &Comparison::Exact, test_cases)
+    }
+
+    fn generate_with(
+        signature: Signature,
+        comparison: &Comparison,
+        test_cases: &[TestCase],
+    ) -> String {
+        CCodeGenerator
+            .generate(&HarnessSpec {
+                code: "",
+                function: "f",
+                signature: &signature,
+                comparison,
+                test_cases,
+                benchmark: None,
+            })
+            .unwrap()
+    }
+
+    /// 参数与返回值同为 ty 的单用例
+    fn identity(ty: &str, input: Value, expected: Value) -> (Signature, Vec<TestCase>) {
+        let ty = ValueType::try_from(ty.to_string()).unwrap();
+        let signature = Signature {
+            params: vec![ty.clone()],
+            returns: ty,
+        };
+        let cases = vec![TestCase {
+            input: vec![input],
+            expected,
+            size: None,
+        }];
+        (signature, cases)
+    }
+
+    #[test]
+    fn renders_scalar_literals() {
+        assert_eq!(scalar_literal(&ValueType::Double, &json!(1)), "1.0");
+        assert_eq!(scalar_literal(&ValueType::Double, &json!(0.1)), "0.1");
+        assert_eq!(
+            scalar_literal(&ValueType::Double, &json!(-2.5e-8)),
+            "-2.5e-8"
+        );
+        assert_eq!(scalar_literal(&ValueType::Long, &json!(-7)), "-7LL");
+        assert_eq!(scalar_literal(&ValueType::Bool, &json!(true)), "true");
+        assert_eq!(
+            scalar_literal(&ValueType::String, &json!("a\"b\\\n\u{1}")),
+            "\"a\\\"b\\\\\\n\\001\""
+        );
+    }
+
+    #[test]
+    fn declares_arrays_with_size_arguments() {
+        let mut out = String::new();
+        let args = declare_array(
+            &mut out,
+            "arg0",
+            &ValueType::try_from("int[][]".to_string()).unwrap(),
+            &json!([[1, 2], [3]]),
+        );
+        assert_eq!(args, vec!["arg0", "2", "arg0_cols"]);
+        assert_eq!(
+            out,
+            "        int arg0_0[] = {1, 2};\n\
+             \x20       int arg0_1[] = {3};\n\
+             \x20       int* arg0[] = {arg0_0, arg0_1};\n\
+             \x20       int arg0_cols[] = {2, 1};\n"
+        );
+
+        let mut out = String::new();
+        let args = declare_array(
+            &mut out,
+            "arg1",
+            &ValueType::try_from("string[]".to_string()).unwrap(),
+            &json!([]),
+        );
+        assert_eq!(args, vec!["arg1", "0"]);
+        assert_eq!(out, "        char** arg1 = NULL;\n");
+    }
+
+    #[test]
+    fn passes_strings_with_byte_length() {
+        let signature = Signature {
+            params: vec![ValueType::String, ValueType::Double],
+            returns: ValueType::Bool,
+        };
+        let cases = vec![TestCase {
+            input: vec![json!("héllo"), json!(2)],
+            expected: json!(true),
+            size: None,
+        }];
+
+        let generated = generate(signature, &cases);
+        assert!(generated.contains("        bool expected = true;\n"));
+        assert!(generated.contains("        bool result = f(\"héllo\", 6, 2.0);\n"));
+    }
+
+    #[test]
+    fn builds_tree_with_nulls_and_empty_list() {
+        let signature = Signature {
+            params: vec![ValueType::Tree, ValueType::List],
+            returns: ValueType::Tree,
+        };
+        let cases = vec![TestCase {
+            input: vec![json!([1, null, 2]), json!([])],
+            expected: json!([1, null, 2, null]),
+            size: None,
+        }];
+
+        let generated = generate(signature, &cases);
+        assert!(generated.contains(
+            "struct TreeNode* build_tree(const int* vals, const bool* nulls, int n) {\n"
+        ));
+        assert!(generated.contains("struct ListNode* build_list(const int* vals, int n) {\n"));
+        assert!(generated.contains("void print_tree(FILE* out, struct TreeNode* root) {\n"));
+        assert!(generated.contains("int tree_equal(struct TreeNode* a, struct TreeNode* b) {\n"));
+        assert!(generated.contains("void print_list(FILE* out, struct ListNode* head) {\n"));
+        assert!(generated.contains("int list_equal(struct ListNode* a, struct ListNode* b) {\n"));
+        assert!(generated.contains(
+            "        int arg0_vals[] = {1, 0, 2};\n\
+             \x20       bool arg0_nulls[] = {false, true, false};\n\
+             \x20       struct TreeNode* arg0 = build_tree(arg0_vals, arg0_nulls, 3);\n\
+             \x20       int* arg1_vals = NULL;\n\
+             \x20       struct ListNode* arg1 = build_list(arg1_vals, 0);\n"
+        ));
+        // 期望值末尾的 null 去掉后再构造
+        assert!(generated.contains(
+            "        struct TreeNode* expected = build_tree(expected_vals, expected_nulls, 3);\n"
+        ));
+        assert!(generated.contains("        int passed = tree_equal(result, expected);\n"));
+        assert!(generated.contains("        print_tree(harness_out, result);\n"));
+    }
+
+    #[test]
+    fn emits_checker_for_each_comparison() {
+        let (signature, cases) = identity("double[]", json!([1.0, 2.5]), json!([1.0, 2.5]));
+        let epsilon = Comparison::Epsilon {
+            abs: 1e-6,
+            rel: 0.001,
+        };
+        let generated = generate_with(signature, &epsilon, &cases);
+        assert!(generated.contains(
+            "int approx_equal(double a, double b) {\n\
+             \x20   double diff = a > b ? a - b : b - a;\n\
+             \x20   double scale = b < 0 ? -b : b;\n\
+             \x20   return diff <= 1e-6 || diff <= 0.001 * scale;\n"
+        ));
+        assert!(generated.contains("            passed = approx_equal(result[i], expected[i]);\n"));
+
+        let (signature, cases) = identity("int[]", json!([3, 1, 2]), json!([1, 2, 3]));
+        let generated = generate_with(signature, &Comparison::Unordered, &cases);
+        assert!(generated.contains("int normalize_items(int* items, int n, int** out) {\n"));
+        assert!(generated.contains("    return (x > y) - (x < y);\n"));
+        assert!(!generated.contains("int m = 0;"));
+        assert!(generated.contains(
+            "        int sorted_result_size = normalize_items(result, result_size, &sorted_result);\n\
+             \x20       int sorted_expected_size = normalize_items(expected, 3, &sorted_expected);\n\
+             \x20       int passed = sorted_result_size == sorted_expected_size;\n"
+        ));
+        assert!(
+            generated.contains("            passed = sorted_result[i] == sorted_expected[i];\n")
+        );
+
+        let (signature, cases) = identity("string[]", json!(["b", "a", "a"]), json!(["a", "b"]));
+        let generated = generate_with(signature, &Comparison::Set, &cases);
+        assert!(generated.contains("int normalize_items(char** items, int n, char*** out) {\n"));
+        assert!(generated.contains("    return strcmp(x, y);\n"));
+        assert!(generated.contains(
+            "        if (m == 0 || compare_items(&(*out)[m - 1], &(*out)[i]) != 0) (*out)[m++] = (*out)[i];\n"
+        ));
+        assert!(generated.contains(
+            "        int sorted_expected_size = normalize_items(expected, 2, &sorted_expected);\n"
+        ));
+
+        let (signature, cases) = identity("string", json!("Hello"), json!("hELLO"));
+        let generated = generate_with(signature, &Comparison::CaseInsensitive, &cases);
+        assert!(generated.contains(
+            "        int passed = (result != NULL && strcasecmp(result, expected) == 0);\n"
+        ));
+    }
+
+    fn generate_bench(signature: &Signature, test_cases: &[TestCase]) -> Result<String> {
+        CCodeGenerator.generate(&HarnessSpec {
+            code: "",
+            function: "f",
+            signature,
+            comparison: &Comparison::Exact,
+            test_cases,
+            benchmark: Some(&BenchmarkOptions {
+                warmup_ms: 20.0,
+                min_time_ms: 100.0,
+            }),
+        })
+    }
+
+    #[test]
+    fn bench_copies_array_args_outside_timed_region() {
+        let signature = Signature {
+            params: vec![
+                ValueType::try_from("int[]".to_string()).unwrap(),
+                ValueType::try_from("int[][]".to_string()).unwrap(),
+            ],
+            returns: ValueType::try_from("int[]".to_string()).unwrap(),
+        };
+        let cases = vec![TestCase {
+            input: vec![json!([1, 2, 3]), json!([[1], [2, 3], []])],
+            expected: json!([1, 2, 3]),
+            size: None,
+        }];
+        let code = generate_bench(&signature, &cases).unwrap();
+
+        // 原始字面量单独声明，第一次调用改写 arg0 也不影响副本
+        assert!(code.contains("        int bench_arg0_src[] = {1, 2, 3};\n"));
+        assert!(code.contains("        int bench_arg1_src_cols[] = {1, 2, 0};\n"));
+        assert!(code.contains(
+            "        int* bench_arg0 = malloc(sizeof(bench_arg0_src) * bench_max_batch);\n"
+        ));
+        assert!(
+            code.contains(
+                "        int** bench_arg1 = malloc(sizeof(int*) * 3 * bench_max_batch);\n"
+            )
+        );
+
+        // 每批先复制参数，再开始计时
+        let fill = code
+            .find("for (long long k = 0; k < bench_batch; k++) { memcpy(bench_arg0 + k * 3, bench_arg0_src, sizeof(bench_arg0_src));")
+            .unwrap();
+        let start = code[fill..].find("double bench_sample_start").unwrap() + fill;
+        let call = code
+            .find("bench_result = f(bench_arg0 + k * 3, 3, bench_arg1 + k * 3, 3, bench_arg1_cols + k * 3, &bench_size);")
+            .unwrap();
+        assert!(fill < start && start < call);
+
+        // 返回的内存不释放，改为限制堆的增长
+        assert!(!code.contains("free(bench_result"));
+        assert!(code.contains("#include <malloc.h>\n"));
+        assert!(code.contains("bench_leaked = bench_heap_bytes() - bench_heap_start;"));
+        assert!(code.contains("&& bench_leaked < 33554432"));
+        assert!(code.contains(
+            " free(bench_arg0); free(bench_arg1); free(bench_arg1_cols); free(bench_arg1_data);\n"
+        ));
+    }
+
+    #[test]
+    fn bench_rejects_node_params() {
+        let signature = Signature {
+            params: vec![ValueType::List],
+            returns: ValueType::Int,
+        };
+        let cases = vec![TestCase {
+            input: vec![json!([1, 2])],
+            expected: json!(1),
+            size: None,
+        }];
+        assert!(generate_bench(&signature, &cases).is_err());
+    }
+}
@@ -1,59 +1,617 @@
-use crate::codegen::CodeGenerator;
-use crate::model::request::TestCase;
+use crate::codegen::{
+    BENCH_MAX_SAMPLES, BENCH_TARGET_SAMPLES, C_NONCE_READER, CodeGenerator, HarnessSpec,
+    bench_max_batch, c_string_literal, ensure_bench_args_copyable,
+};
+use crate::model::comparison::Comparison;
+use crate::model::request::BenchmarkOptions;
+use crate::model::signature::ValueType;
 use anyhow::Result;
+use serde_json::Value;
 
+/// 数组使用 std::vector，字符串使用 std::string，可直接用 == 比较
 pub struct CppCodeGenerator;
 
 impl CodeGenerator for CppCodeGenerator {
-    fn generate(&self, code: &str, function: &str, test_cases: &[TestCase]) -> Result<String> {
-        let mut full = String::with_capacity(code.len() + test_cases.len() * 300 + 200);
+    fn generate(&self, spec: &HarnessSpec) -> Result<String> {
+        let signature = spec.signature;
+        ensure_bench_args_copyable(spec, "C++")?;
+        let mut full = String::with_capacity(spec.code.len() + spec.test_cases.len() * 400 + 1200);
 
         full.push_str("#include <iostream>\n");
+        full.push_str("#include <iomanip>\n");
+        full.push_str("#include <sstream>\n");
+        full.push_str("#include <cstdio>\n");
+        full.push_str("#include <cstring>\n");
+        full.push_str("#include <cmath>\n");
+        full.push_str("#include <string>\n");
+        full.push_str("#include <vector>\n");
         full.push_str("#include <chrono>\n");
-        full.push_str("#include <sys/resource.h>\n");
+        if signature.uses(&ValueType::Tree) {
+            full.push_str("#include <optional>\n");
+        }
+        if spec.benchmark.is_some()
+            || matches!(
+                spec.comparison,
+                Comparison::Unordered | Comparison::Set | Comparison::CaseInsensitive
+            )
+        {
+            full.push_str("#include <algorithm>\n");
+        }
         full.push_str("using namespace std;\n");
         full.push_str("using namespace std::chrono;\n\n");
-        full.push_str(code);
-        full.push_str("\n\nlong get_memory_kb() {\n");
-        full.push_str("    struct rusage usage;\n");
-        full.push_str("    getrusage(RUSAGE_SELF, &usage);\n");
-        full.push_str("    return usage.ru_maxrss;\n");
+        // 结构体定义需在用户代码之前
+        if signature.uses(&ValueType::List) {
+            full.push_str(LIST_PRELUDE);
+        }
+        if signature.uses(&ValueType::Tree) {
+            full.push_str(TREE_PRELUDE);
+        }
+        full.push_str(spec.code);
+        full.push_str("\n\n");
+        full.push_str(C_NONCE_READER);
+
+        // 以 JSON 格式输出返回值
+        full.push_str("void print_json(ostream& os, int v) { os << v; }\n");
+        full.push_str("void print_json(ostream& os, long v) { os << v; }\n");
+        full.push_str("void print_json(ostream& os, long long v) { os << v; }\n");
+        // nan / inf 不是合法的 JSON，输出为字符串
+        full.push_str("void print_json(ostream& os, double v) {\n");
+        full.push_str("    if (isnan(v)) os << \"\\\"NaN\\\"\";\n");
+        full.push_str("    else if (isinf(v)) os << (v > 0 ? \"\\\"Infinity\\\"\" : \"\\\"-Infinity\\\"\");\n");
+        full.push_str("    else os << setprecision(15) << v;\n");
+        full.push_str("}\n");
+        full.push_str(
+            "void print_json(ostream& os, bool v) { os << (v ? \"true\" : \"false\"); }\n",
+        );
+        full.push_str("void print_json(ostream& os, const string& s) {\n");
+        full.push_str("    os << '\"';\n");
+        full.push_str("    for (unsigned char c : s) {\n");
+        full.push_str("        if (c == '\"' || c == '\\\\') os << '\\\\' << c;\n");
+        full.push_str("        else if (c == '\\n') os << \"\\\\n\";\n");
+        full.push_str("        else if (c < 0x20) os << \"\\\\u\" << hex << setw(4) << setfill('0') << (int)c << dec;\n");
+        full.push_str("        else os << c;\n");
+        full.push_str("    }\n");
+        full.push_str("    os << '\"';\n");
+        full.push_str("}\n");
+        if signature.uses(&ValueType::Tree) {
+            full.push_str("void print_json(ostream& os, const optional<int>& v) {\n");
+            full.push_str("    if (v) os << *v;\n");
+            full.push_str("    else os << \"null\";\n");
+            full.push_str("}\n");
+        }
+        full.push_str("template <typename T>\n");
+        full.push_str("void print_json(ostream& os, const vector<T>& v) {\n");
+        full.push_str("    os << '[';\n");
+        full.push_str("    for (size_t i = 0; i < v.size(); i++) {\n");
+        full.push_str("        if (i) os << ',';\n");
+        full.push_str("        print_json(os, v[i]);\n");
+        full.push_str("    }\n");
+        full.push_str("    os << ']';\n");
         full.push_str("}\n\n");
+        push_checker(&mut full, spec.comparison);
+        if spec.benchmark.is_some() {
+            // 空的内联汇编让编译器认为结果被读取，调用不会被优化掉
+            full.push_str("template <typename T>\n");
+            full.push_str("void bench_keep(const T& value) { asm volatile(\"\" : : \"r,m\"(value) : \"memory\"); }\n");
+            full.push_str("double bench_elapsed_ms(steady_clock::time_point start) {\n");
+            full.push_str(
+                "    return duration<double, milli>(steady_clock::now() - start).count();\n",
+            );
+            full.push_str("}\n\n");
+        }
 
-        full.push_str("int main() {\n");
-        full.push_str("    auto program_start = steady_clock::now();\n");
-        full.push_str("    long start_mem = get_memory_kb();\n\n");
+        full.push_str("int main(int argc, char** argv) {\n");
+        full.push_str("    FILE* harness_out = fdopen(3, \"w\");\n");
+        full.push_str("    if (!harness_out) {\n");
+        full.push_str("        cerr << \"results fd 3 is not open\" << endl;\n");
+        full.push_str("        return 1;\n");
+        full.push_str("    }\n");
+        full.push_str("    int harness_first = argc > 2 ? stoi(argv[1]) : 0;\n");
+        full.push_str(&format!(
+            "    int harness_last = argc > 2 ? stoi(argv[2]) : {};\n",
+            spec.test_cases.len()
+        ));
+        full.push_str("    auto program_start = steady_clock::now();\n\n");
+
+        for (index, t) in spec.test_cases.iter().enumerate() {
+            let mut args = Vec::new();
+            let mut bench_args = Vec::new();
+            // 基准测试需要为每次调用复制的参数：变量名、类型与值
+            let mut copied = Vec::new();
+            let expected = signature.returns.canonical(&t.expected);
+
+            full.push_str(&format!(
+                "    if (harness_first <= {0} && {0} < harness_last) {{\n",
+                index
+            ));
+            // 容器类型先声明为变量，以便传给非 const 引用参数
+            for (j, (ty, value)) in signature.params.iter().zip(&t.input).enumerate() {
+                if ty.is_scalar() && *ty != ValueType::String {
+                    args.push(literal(ty, value));
+                    bench_args.push(literal(ty, value));
+                } else {
+                    let name = format!("arg{}", j);
+                    full.push_str(&format!(
+                        "        {} {} = {};\n",
+                        cpp_type(ty),
+                        name,
+                        literal(ty, value)
+                    ));
+                    // 在第一次调用之前保存原始参数，基准测试从它复制
+                    if spec.benchmark.is_some() {
+                        full.push_str(&format!(
+                            "        const {} bench_{}_src = {};\n",
+                            cpp_type(ty),
+                            name,
+                            name
+                        ));
+                        bench_args.push(format!("bench_{}[k]", name));
+                        copied.push((name.clone(), cpp_type(ty), value));
+                    }
+                    args.push(name);
+                }
+            }
+            // 链表和二叉树返回值先序列化为数组，再与期望值比较
+            let expected_type = match signature.returns {
+                ValueType::List => "vector<int>".to_string(),
+                ValueType::Tree => "vector<optional<int>>".to_string(),
+                ref ty => cpp_type(ty),
+            };
+            full.push_str(&format!(
+                "        {} expected = {};\n",
+                expected_type,
+                match signature.returns {
+                    ValueType::List | ValueType::Tree => level_order(&expected),
+                    ref ty => literal(ty, &expected),
+                }
+            ));
 
-        for t in test_cases {
-            let args = t
-                .input
-                .iter()
-                .map(|x| x.to_string())
-                .collect::<Vec<_>>()
-                .join(", ");
-            full.push_str("    {\n");
             full.push_str("        auto start = steady_clock::now();\n");
-            full.push_str(&format!("        int result = {}({});\n", function, args));
+            full.push_str(&format!(
+                "        auto result = {}({});\n",
+                spec.function,
+                args.join(", ")
+            ));
             full.push_str("        auto end = steady_clock::now();\n");
             full.push_str("        double time_ms = duration_cast<microseconds>(end - start).count() / 1000.0;\n");
+            let actual = if signature.returns.is_node() {
+                full.push_str("        auto actual = serialize(result);\n");
+                "actual"
+            } else {
+                "result"
+            };
+            let passed = match spec.comparison {
+                Comparison::Exact => format!("{} == expected", actual),
+                _ => format!("compare_result({}, expected)", actual),
+            };
+            full.push_str(&format!("        bool passed = {};\n", passed));
+            if let Some(benchmark) = spec.benchmark {
+                push_bench(
+                    &mut full,
+                    benchmark,
+                    &format!("{}({})", spec.function, bench_args.join(", ")),
+                    &copied,
+                );
+            }
+            full.push_str("        ostringstream record;\n");
             full.push_str(&format!(
-                "        cout << \"Input: ({}) => Output: \" << result << \" | Expected: {} | \" \
-                 << (result == {} ? \"✅ PASS\" : \"❌ FAIL\") << \" | Time: \" << time_ms << \"ms\" << endl;\n",
-                args, t.expected, t.expected
+                "        record << \"{{\\\"nonce\\\":\\\"\" << harness_nonce << \"\\\",\\\"index\\\":{},\\\"passed\\\":\" << (passed ? \"true\" : \"false\") << \",\\\"time_ms\\\":\" << time_ms << \",\\\"output\\\":\";\n",
+                index
             ));
+            full.push_str(&format!("        print_json(record, {});\n", actual));
+            if spec.benchmark.is_some() {
+                full.push_str("        record << \",\\\"bench\\\":{\\\"iterations\\\":\" << bench_iterations << \",\\\"samples\\\":\";\n");
+                full.push_str("        print_json(record, bench_samples);\n");
+                full.push_str("        record << \"}\";\n");
+            }
+            full.push_str("        record << \"}\\n\";\n");
+            full.push_str("        fputs(record.str().c_str(), harness_out);\n");
+            full.push_str("        fflush(harness_out);\n");
             full.push_str("    }\n\n");
         }
 
         full.push_str("    auto program_end = steady_clock::now();\n");
-        full.push_str("    long end_mem = get_memory_kb();\n");
         full.push_str("    double total_time = duration_cast<microseconds>(program_end - program_start).count() / 1000.0;\n");
-        full.push_str("    cout << \"\\n=== Performance ===\" << endl;\n");
-        full.push_str("    cout << \"Time: \" << total_time << \"ms\" << endl;\n");
-        full.push_str("    cout << \"Memory: \" << end_mem << \" KB\" << endl;\n");
+        full.push_str("    ostringstream record;\n");
+        full.push_str("    record << \"{\\\"nonce\\\":\\\"\" << harness_nonce << \"\\\",\\\"done\\\":true,\\\"time_ms\\\":\" << total_time << \"}\\n\";\n");
+        full.push_str("    fputs(record.str().c_str(), harness_out);\n");
+        full.push_str("    fclose(harness_out);\n");
 
         full.push_str("    return 0;\n");
         full.push('}');
 
         Ok(full)
     }
-}
\ No newline at end of file
+}
+
+/// 基准测试：预热时每轮调用次数翻倍，直到用满 warmup_ms，由此估出单次耗时；
+/// 之后按估出的单次耗时分批调用，直到测量时间达到 min_time_ms，每批的平均耗时为一个样本
+///
+/// 函数可能改写引用传入的参数，每批调用前在计时之外为每次调用复制一份原始参数，
+/// 每批的调用次数受副本大小限制。copied 为需要复制的参数：变量名、类型与值。
+fn push_bench(
+    out: &mut String,
+    benchmark: &BenchmarkOptions,
+    call: &str,
+    copied: &[(String, String, &Value)],
+) {
+    out.push_str(&format!(
+        "        const long long bench_max_batch = {}LL;\n",
+        bench_max_batch(copied.iter().map(|(_, _, value)| *value))
+    ));
+    for (name, ty, _) in copied {
+        out.push_str(&format!("        vector<{}> bench_{};\n", ty, name));
+    }
+    out.push_str("        vector<double> bench_samples;\n");
+    out.push_str("        long long bench_iterations = 0, bench_calls = 0, bench_batch = 1;\n");
+    out.push_str("        double bench_elapsed = 0;\n");
+
+    let mut batch = String::new();
+    for (name, _, _) in copied {
+        batch.push_str(&format!(
+            "            bench_{0}.assign(bench_batch, bench_{0}_src);\n",
+            name
+        ));
+    }
+    batch.push_str("            auto bench_sample_start = steady_clock::now();\n");
+    batch.push_str(&format!(
+        "            for (long long k = 0; k < bench_batch; k++) bench_keep({});\n",
+        call
+    ));
+    batch.push_str("            double bench_sample_ms = bench_elapsed_ms(bench_sample_start);\n");
+
+    out.push_str("        auto bench_start = steady_clock::now();\n");
+    out.push_str("        do {\n");
+    out.push_str(&batch);
+    out.push_str("            bench_elapsed += bench_sample_ms;\n");
+    out.push_str("            bench_calls += bench_batch;\n");
+    out.push_str("            bench_batch = min(bench_batch * 2, bench_max_batch);\n");
+    out.push_str(&format!(
+        "        }} while (bench_elapsed_ms(bench_start) < {:?});\n",
+        benchmark.warmup_ms
+    ));
+    out.push_str("        double bench_per_call = max(bench_elapsed / bench_calls, 1e-6);\n");
+    out.push_str(&format!(
+        "        bench_batch = clamp((long long)({:?} / {} / bench_per_call), 1LL, bench_max_batch);\n",
+        benchmark.min_time_ms, BENCH_TARGET_SAMPLES
+    ));
+    out.push_str("        bench_start = steady_clock::now();\n");
+    out.push_str(&format!(
+        "        while (bench_samples.size() < {} && (bench_samples.empty() || bench_elapsed_ms(bench_start) < {:?})) {{\n",
+        BENCH_MAX_SAMPLES, benchmark.min_time_ms
+    ));
+    out.push_str(&batch);
+    out.push_str("            bench_samples.push_back(bench_sample_ms / bench_batch);\n");
+    out.push_str("            bench_iterations += bench_batch;\n");
+    out.push_str("        }\n");
+}
+
+fn push_checker(out: &mut String, comparison: &Comparison) {
+    match comparison {
+        Comparison::Exact => {}
+        Comparison::Epsilon { abs, rel } => {
+            out.push_str("bool compare_result(double a, double b) {\n");
+            out.push_str(&format!(
+                "    return fabs(a - b) <= {:?} || fabs(a - b) <= {:?} * fabs(b);\n",
+                abs, rel
+            ));
+            out.push_str("}\n");
+            out.push_str("template <typename T>\n");
+            out.push_str("bool compare_result(const vector<T>& a, const vector<T>& b) {\n");
+            out.push_str("    if (a.size() != b.size()) return false;\n");
+            out.push_str("    for (size_t i = 0; i < a.size(); i++) {\n");
+            out.push_str("        if (!compare_result(a[i], b[i])) return false;\n");
+            out.push_str("    }\n");
+            out.push_str("    return true;\n");
+            out.push_str("}\n\n");
+        }
+        Comparison::Unordered | Comparison::Set => {
+            out.push_str("template <typename T>\n");
+            out.push_str("bool compare_result(vector<T> a, vector<T> b) {\n");
+            out.push_str("    sort(a.begin(), a.end());\n");
+            out.push_str("    sort(b.begin(), b.end());\n");
+            if *comparison == Comparison::Set {
+                out.push_str("    a.erase(unique(a.begin(), a.end()), a.end());\n");
+                out.push_str("    b.erase(unique(b.begin(), b.end()), b.end());\n");
+            }
+            out.push_str("    return a == b;\n");
+            out.push_str("}\n\n");
+        }
+        Comparison::CaseInsensitive => {
+            out.push_str("bool compare_result(const string& a, const string& b) {\n");
+            out.push_str("    return a.size() == b.size() && equal(a.begin(), a.end(), b.begin(), [](unsigned char x, unsigned char y) {\n");
+            out.push_str("        return tolower(x) == tolower(y);\n");
+            out.push_str("    });\n");
+            out.push_str("}\n\n");
+        }
+    }
+}
+
+fn cpp_type(ty: &ValueType) -> String {
+    match ty {
+        ValueType::Int => "int".to_string(),
+        ValueType::Long => "long long".to_string(),
+        ValueType::Double => "double".to_string(),
+        ValueType::Bool => "bool".to_string(),
+        ValueType::String => "string".to_string(),
+        ValueType::List => "ListNode*".to_string(),
+        ValueType::Tree => "TreeNode*".to_string(),
+        ValueType::Array(inner) => format!("vector<{}>", cpp_type(inner)),
+    }
+}
+
+/// 渲染为 C++ 字面量，数组使用花括号初始化列表
+fn literal(ty: &ValueType, value: &Value) -> String {
+    match ty {
+        ValueType::Long => format!("{}LL", value),
+        ValueType::Double => format!("{:?}", value.as_f64().unwrap_or_default()),
+        ValueType::String => c_string_literal(value.as_str().unwrap_or_default()),
+        ValueType::List => format!("build_list({})", level_order(value)),
+        ValueType::Tree => format!("build_tree({})", level_order(value)),
+        ValueType::Array(inner) => {
+            let items = value
+                .as_array()
+                .map(|items| {
+                    items
+                        .iter()
+                        .map(|v| literal(inner, v))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+            format!("{{{}}}", items)
+        }
+        _ => value.to_string(),
+    }
+}
+
+/// 层序数组的初始化列表，null 渲染为 nullopt
+fn level_order(value: &Value) -> String {
+    let items = value
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .map(|v| {
+                    if v.is_null() {
+                        "nullopt".to_string()
+                    } else {
+                        v.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .unwrap_or_default();
+    format!("{{{}}}", items)
+}
+
+/// LeetCode 的链表定义，以及与数组互相转换的函数
+const LIST_PRELUDE: &str = r#"struct ListNode {
+    int val;
+    ListNode *next;
+    ListNode() : val(0), next(nullptr) {}
+    ListNode(int x) : val(x), next(nullptr) {}
+    ListNode(int x, ListNode *next) : val(x), next(next) {}
+};
+
+ListNode* build_list(const vector<int>& vals) {
+    ListNode* head = nullptr;
+    for (auto it = vals.rbegin(); it != vals.rend(); ++it) head = new ListNode(*it, head);
+    return head;
+}
+
+vector<int> serialize(ListNode* head) {
+    vector<int> vals;
+    for (; head; head = head->next) vals.push_back(head->val);
+    return vals;
+}
+
+"#;
+
+/// LeetCode 的二叉树定义，层序数组中的 null 对应 nullopt
+const TREE_PRELUDE: &str = r#"struct TreeNode {
+    int val;
+    TreeNode *left;
+    TreeNode *right;
+    TreeNode() : val(0), left(nullptr), right(nullptr) {}
+    TreeNode(int x) : val(x), left(nullptr), right(nullptr) {}
+    TreeNode(int x, TreeNode *left, TreeNode *right) : val(x), left(left), right(right) {}
+};
+
+TreeNode* build_tree(const vector<optional<int>>& vals) {
+    if (vals.empty() || !vals[0]) return nullptr;
+    vector<TreeNode*> nodes;
+    for (auto& v : vals) nodes.push_back(v ? new TreeNode(*v) : nullptr);
+    for (size_t i = 0, child = 1; i < nodes.size() && child < nodes.size(); i++) {
+        if (!nodes[i]) continue;
+        nodes[i]->left = nodes[child++];
+        if (child < nodes.size()) nodes[i]->right = nodes[child++];
+    }
+    return nodes[0];
+}
+
+vector<optional<int>> serialize(TreeNode* root) {
+    vector<TreeNode*> queue;
+    if (root) queue.push_back(root);
+    for (size_t i = 0; i < queue.size(); i++) {
+        if (!queue[i]) continue;
+        queue.push_back(queue[i]->left);
+        queue.push_back(queue[i]->right);
+    }
+    while (!queue.empty() && !queue.back()) queue.pop_back();
+    vector<optional<int>> vals;
+    for (auto node : queue) vals.push_back(node ? optional<int>(node->val) : nullopt);
+    return vals;
+}
+
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::request::TestCase;
+    use crate::model::signature::Signature;
+    use serde_json::json;
+
+    fn generate(signature: &Signature, comparison: &Comparison, test_cases: &[TestCase]) -> String {
+        CppCodeGenerator
+            .generate(&HarnessSpec {
+                code: "",
+                function: "f",
+                signature,
+                comparison,
+                test_cases,
+                benchmark: None,
+            })
+            .unwrap()
+    }
+
+    /// 参数与返回值同为 ty 的单用例，只关心比较代码
+    fn generate_identity(ty: &str, value: Value, comparison: &Comparison) -> String {
+        let ty = ValueType::try_from(ty.to_string()).unwrap();
+        let signature = Signature {
+            params: vec![ty.clone()],
+            returns: ty,
+        };
+        let cases = vec![TestCase {
+            input: vec![value.clone()],
+            expected: value,
+            size: None,
+        }];
+        generate(&signature, comparison, &cases)
+    }
+
+    #[test]
+    fn builds_tree_with_nulls_and_empty_list() {
+        let signature = Signature {
+            params: vec![ValueType::Tree, ValueType::List],
+            returns: ValueType::Tree,
+        };
+        let cases = vec![TestCase {
+            input: vec![json!([1, null, 2]), json!([])],
+            expected: json!([1, null, 2, null]),
+            size: None,
+        }];
+
+        let generated = generate(&signature, &Comparison::Exact, &cases);
+        assert!(generated.contains("ListNode* build_list(const vector<int>& vals) {\n"));
+        assert!(generated.contains("vector<int> serialize(ListNode* head) {\n"));
+        assert!(generated.contains("TreeNode* build_tree(const vector<optional<int>>& vals) {\n"));
+        assert!(generated.contains("vector<optional<int>> serialize(TreeNode* root) {\n"));
+        // 期望值末尾的 null 去掉后与序列化结果比较
+        assert!(generated.contains("        vector<optional<int>> expected = {1, nullopt, 2};\n"));
+        assert!(
+            generated.contains(
+                "        auto result = f(build_tree({1, nullopt, 2}), build_list({}));\n"
+            )
+        );
+        assert!(generated.contains(
+            "        auto actual = serialize(result);\n\
+             \x20       bool passed = actual == expected;\n"
+        ));
+        assert!(generated.contains("print_json(record, actual);"));
+    }
+
+    #[test]
+    fn emits_checker_for_each_comparison() {
+        let epsilon = Comparison::Epsilon {
+            abs: 1e-6,
+            rel: 0.001,
+        };
+        let generated = generate_identity("double[]", json!([1.0, 2.5]), &epsilon);
+        assert!(generated.contains(
+            "bool compare_result(double a, double b) {\n\
+             \x20   return fabs(a - b) <= 1e-6 || fabs(a - b) <= 0.001 * fabs(b);\n\
+             }\n\
+             template <typename T>\n\
+             bool compare_result(const vector<T>& a, const vector<T>& b) {\n"
+        ));
+        assert!(generated.contains("        bool passed = compare_result(result, expected);\n"));
+
+        let generated = generate_identity("int[]", json!([3, 1, 2]), &Comparison::Unordered);
+        assert!(generated.contains(
+            "bool compare_result(vector<T> a, vector<T> b) {\n\
+             \x20   sort(a.begin(), a.end());\n\
+             \x20   sort(b.begin(), b.end());\n\
+             \x20   return a == b;\n"
+        ));
+
+        let generated = generate_identity("string[]", json!(["b", "a"]), &Comparison::Set);
+        assert!(generated.contains(
+            "    a.erase(unique(a.begin(), a.end()), a.end());\n\
+             \x20   b.erase(unique(b.begin(), b.end()), b.end());\n\
+             \x20   return a == b;\n"
+        ));
+
+        let generated = generate_identity("string", json!("Hello"), &Comparison::CaseInsensitive);
+        assert!(generated.contains("bool compare_result(const string& a, const string& b) {\n"));
+        assert!(generated.contains("        return tolower(x) == tolower(y);\n"));
+
+        let generated = generate_identity("int", json!(1), &Comparison::Exact);
+        assert!(!generated.contains("compare_result"));
+        assert!(generated.contains("        bool passed = result == expected;\n"));
+    }
+
+    fn generate_bench(signature: &Signature, test_cases: &[TestCase]) -> Result<String> {
+        CppCodeGenerator.generate(&HarnessSpec {
+            code: "",
+            function: "f",
+            signature,
+            comparison: &Comparison::Exact,
+            test_cases,
+            benchmark: Some(&BenchmarkOptions {
+                warmup_ms: 20.0,
+                min_time_ms: 100.0,
+            }),
+        })
+    }
+
+    #[test]
+    fn bench_copies_args_outside_timed_region() {
+        let signature = Signature {
+            params: vec![
+                ValueType::try_from("int[]".to_string()).unwrap(),
+                ValueType::Int,
+            ],
+            returns: ValueType::Int,
+        };
+        let cases = vec![TestCase {
+            input: vec![json!([3, 1, 2]), json!(5)],
+            expected: json!(1),
+            size: None,
+        }];
+        let code = generate_bench(&signature, &cases).unwrap();
+
+        // 在第一次调用之前保存原始参数
+        let src = code
+            .find("        const vector<int> bench_arg0_src = arg0;\n")
+            .unwrap();
+        assert!(src < code.find("auto result = f(arg0, 5);").unwrap());
+        assert!(code.contains("        vector<vector<int>> bench_arg0;\n"));
+
+        // 每批先复制参数，再开始计时
+        let fill = code
+            .find("            bench_arg0.assign(bench_batch, bench_arg0_src);\n            auto bench_sample_start = steady_clock::now();\n")
+            .unwrap();
+        let call = code
+            .find("for (long long k = 0; k < bench_batch; k++) bench_keep(f(bench_arg0[k], 5));")
+            .unwrap();
+        assert!(fill < call);
+        assert!(code.contains("bench_batch = min(bench_batch * 2, bench_max_batch);"));
+    }
+
+    #[test]
+    fn bench_rejects_node_params() {
+        let signature = Signature {
+            params: vec![ValueType::Tree],
+            returns: ValueType::Int,
+        };
+        let cases = vec![TestCase {
+            input: vec![json!([1, null, 2])],
+            expected: json!(1),
+            size: None,
+        }];
+        assert!(generate_bench(&signature, &cases).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use crate::codegen::{CodeGenerator, HarnessSpec, ensure_int_signature, ensure_no_benchmark};
+use anyhow::Result;
+
+/// 测试框架自身依赖的包
+const HARNESS_IMPORTS: [&str; 3] = ["\"fmt\"", "\"os\"", "\"time\""];
+
+pub struct GoCodeGenerator;
+
+impl CodeGenerator for GoCodeGenerator {
+    fn generate(&self, spec: &HarnessSpec) -> Result<String> {
+        ensure_int_signature(spec.signature, "Go")?;
+        ensure_no_benchmark(spec, "Go")?;
+
+        let mut full = String::with_capacity(spec.code.len() + spec.test_cases.len() * 350 + 500);
+
+        // Go 不允许重复导入同一个包，需要把用户的 import 与框架的合并
+        let (imports, body) = split_imports(spec.code);
+
+        full.push_str("package main\n\n");
+        full.push_str("import (\n");
+        for spec in merge_imports(&imports) {
+            full.push_str(&format!("\t{}\n", spec));
+        }
+        full.push_str(")\n\n");
+        full.push_str(body.trim_start_matches('\n'));
+        full.push_str("\n\n");
+
+        // nonce 与结果通道只作为 main 的局部变量存在，不生成任何包级标识符，
+        // 否则同一个包中的用户代码可以直接引用它们伪造记录
+        full.push_str("func main() {\n");
+        full.push_str("\tvar harnessNonce string\n");
+        full.push_str("\tfmt.Fscanln(os.Stdin, &harnessNonce)\n");
+        full.push_str("\tharnessOut := os.NewFile(3, \"results\")\n");
+        full.push_str(&format!(
+            "\tharnessFirst, harnessLast := 0, {}\n",
+            spec.test_cases.len()
+        ));
+        full.push_str("\tif len(os.Args) > 2 {\n");
+        full.push_str("\t\tfmt.Sscan(os.Args[1], &harnessFirst)\n");
+        full.push_str("\t\tfmt.Sscan(os.Args[2], &harnessLast)\n");
+        full.push_str("\t}\n");
+        full.push_str("\tprogramStart := time.Now()\n\n");
+
+        for (index, t) in spec.test_cases.iter().enumerate() {
+            let args = t
+                .input
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            full.push_str(&format!(
+                "\tif harnessFirst <= {0} && {0} < harnessLast {{\n",
+                index
+            ));
+            full.push_str("\t\tstart := time.Now()\n");
+            full.push_str(&format!("\t\tresult := {}({})\n", spec.function, args));
+            full.push_str("\t\ttimeMs := float64(time.Since(start).Nanoseconds()) / 1e6\n");
+            full.push_str(&format!(
+                "\t\tfmt.Fprintf(harnessOut, \"{{\\\"nonce\\\":\\\"%s\\\",\\\"index\\\":{},\\\"passed\\\":%t,\\\"time_ms\\\":%.3f,\\\"output\\\":%d}}\\n\", harnessNonce, result == {}, timeMs, result)\n",
+                index, t.expected
+            ));
+            full.push_str("\t}\n\n");
+        }
+
+        full.push_str("\ttotalTime := float64(time.Since(programStart).Nanoseconds()) / 1e6\n");
+        full.push_str("\tfmt.Fprintf(harnessOut, \"{\\\"nonce\\\":\\\"%s\\\",\\\"done\\\":true,\\\"time_ms\\\":%.3f}\\n\", harnessNonce, totalTime)\n");
+        full.push_str("\tharnessOut.Close()\n");
+        full.push('}');
+
+        Ok(full)
+    }
+}
+
+/// 拆分用户代码头部的 package / import 声明，返回 (import 项, 剩余代码)
+fn split_imports(code: &str) -> (Vec<String>, &str) {
+    let mut imports = Vec::new();
+    let mut in_block = false;
+    let mut offset = 0;
+
+    for line in code.split_inclusive('\n') {
+        let trimmed = line.trim();
+
+        if in_block {
+            if let Some(rest) = trimmed.strip_prefix(')') {
+                in_block = false;
+                if !rest.trim().is_empty() {
+                    // `)` 后同一行还有代码：跳过 `)` 与分隔的分号，只保留其后的代码
+                    let close = offset + line.find(')').unwrap_or(0) + 1;
+                    let body =
+                        code[close..].trim_start_matches(|c: char| c == ';' || c.is_whitespace());
+                    return (imports, body);
+                }
+            } else {
+                push_specs(&mut imports, trimmed);
+            }
+        } else if trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with("package ")
+        {
+            // 空行、注释和 package 声明直接丢弃
+        } else if let Some(rest) = trimmed.strip_prefix("import") {
+            let rest = rest.trim();
+            if let Some(inner) = rest.strip_prefix('(') {
+                match inner.split_once(')') {
+                    Some((specs, _)) => push_specs(&mut imports, specs),
+                    None => {
+                        in_block = true;
+                        push_specs(&mut imports, inner);
+                    }
+                }
+            } else {
+                push_specs(&mut imports, rest);
+            }
+        } else {
+            break;
+        }
+
+        offset += line.len();
+    }
+
+    (imports, &code[offset..])
+}
+
+fn push_specs(imports: &mut Vec<String>, specs: &str) {
+    for spec in specs.split(';') {
+        let spec = spec.split("//").next().unwrap_or("").trim();
+        if !spec.is_empty() {
+            imports.push(spec.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+    }
+}
+
+/// 合并用户与框架的 import，去除完全相同的项（带别名的导入保留）
+fn merge_imports(user_imports: &[String]) -> Vec<String> {
+    let mut merged: Vec<String> = HARNESS_IMPORTS.iter().map(|s| s.to_string()).collect();
+    for spec in user_imports {
+        if !merged.contains(spec) {
+            merged.push(spec.clone());
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::generate_ints;
+    use crate::model::request::TestCase;
+
+    #[test]
+    fn merges_user_imports_without_duplicates() {
+        let code = "package main\n\nimport (\n\t\"fmt\"\n\t\"sort\" // 排序\n)\n\nimport s \"strings\"\n\nfunc f() int {\n\treturn 1\n}\n";
+
+        let generated = generate_ints(&GoCodeGenerator, code, "f", &[]).unwrap();
+
+        assert!(generated.starts_with(
+            "package main\n\nimport (\n\t\"fmt\"\n\t\"os\"\n\t\"time\"\n\t\"sort\"\n\ts \"strings\"\n)\n\nfunc f() int {"
+        ));
+        assert_eq!(generated.matches("package main").count(), 1);
+        assert_eq!(generated.matches("\"fmt\"").count(), 1);
+    }
+
+    #[test]
+    fn keeps_code_after_closing_paren() {
+        let (imports, body) =
+            split_imports("import (\n\t\"sort\"\n) ; func f() int { return 1 }\n");
+
+        assert_eq!(imports, vec!["\"sort\""]);
+        assert_eq!(body, "func f() int { return 1 }\n");
+    }
+
+    #[test]
+    fn declares_no_package_level_harness_identifiers() {
+        let code = "func f() int {\n\treturn 1\n}";
+
+        let generated = generate_ints(&GoCodeGenerator, code, "f", &[]).unwrap();
+        let declarations: Vec<&str> = generated
+            .lines()
+            .filter(|line| {
+                ["var", "const", "type", "func"]
+                    .iter()
+                    .any(|kw| line.starts_with(kw))
+            })
+            .collect();
+
+        assert_eq!(declarations, vec!["func f() int {", "func main() {"]);
+        assert!(generated.contains("func main() {\n\tvar harnessNonce string\n"));
+    }
+
+    #[test]
+    fn generates_case_block() {
+        let cases = vec![TestCase::ints(&[1, 2], 3)];
+
+        let generated = generate_ints(
+            &GoCodeGenerator,
+            "func add(a, b int) int {\n\treturn a + b\n}",
+            "add",
+            &cases,
+        )
+        .unwrap();
+
+        assert!(generated.contains("\t\tresult := add(1, 2)\n"));
+        assert!(generated.contains(
+            "fmt.Fprintf(harnessOut, \"{\\\"nonce\\\":\\\"%s\\\",\\\"index\\\":0,\\\"passed\\\":%t,\\\"time_ms\\\":%.3f,\\\"output\\\":%d}\\n\", harnessNonce, result == 3, timeMs, result)"
+        ));
+    }
+}
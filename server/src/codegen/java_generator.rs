@@ -0,0 +1,144 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::codegen::{CodeGenerator, HarnessSpec, ensure_int_signature, ensure_no_benchmark};
+use anyhow::Result;
+
+pub struct JavaCodeGenerator;
+
+impl CodeGenerator for JavaCodeGenerator {
+    fn generate(&self, spec: &HarnessSpec) -> Result<String> {
+        ensure_int_signature(spec.signature, "Java")?;
+        ensure_no_benchmark(spec, "Java")?;
+
+        static RE_SOLUTION: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"\bclass\s+Solution\b").unwrap());
+
+        let mut full = String::with_capacity(spec.code.len() + spec.test_cases.len() * 400 + 800);
+
+        // import 必须位于文件顶部，不能放进 Solution 类内部
+        let (imports, body) = split_imports(spec.code);
+
+        full.push_str("import java.io.BufferedReader;\n");
+        full.push_str("import java.io.FileOutputStream;\n");
+        full.push_str("import java.io.InputStreamReader;\n");
+        full.push_str("import java.io.PrintStream;\n");
+        full.push_str("import java.nio.charset.StandardCharsets;\n");
+        full.push_str("import java.util.Locale;\n");
+        for line in imports {
+            full.push_str(line);
+            full.push('\n');
+        }
+        full.push('\n');
+
+        // 用户只提交方法时包装进 Solution 类，已写好 Solution 类则原样保留
+        if RE_SOLUTION.is_match(body) {
+            full.push_str(body.trim_start_matches('\n'));
+        } else {
+            full.push_str("class Solution {\n");
+            full.push_str(body.trim_start_matches('\n'));
+            full.push_str("\n}");
+        }
+
+        full.push_str("\n\npublic class Main {\n");
+
+        full.push_str("    public static void main(String[] args) throws Exception {\n");
+        // 在创建 Solution 之前读取 nonce，Java 无法直接使用文件描述符 3，通过 /proc 打开
+        full.push_str("        String nonce = new BufferedReader(new InputStreamReader(System.in, StandardCharsets.UTF_8)).readLine();\n");
+        full.push_str("        PrintStream out = new PrintStream(new FileOutputStream(\"/proc/self/fd/3\", true), true, StandardCharsets.UTF_8);\n");
+        full.push_str(
+            "        int harnessFirst = args.length > 1 ? Integer.parseInt(args[0]) : 0;\n",
+        );
+        full.push_str(&format!(
+            "        int harnessLast = args.length > 1 ? Integer.parseInt(args[1]) : {};\n",
+            spec.test_cases.len()
+        ));
+        full.push_str("        Solution solution = new Solution();\n");
+        full.push_str("        long programStart = System.nanoTime();\n\n");
+
+        for (index, t) in spec.test_cases.iter().enumerate() {
+            let args = t
+                .input
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            full.push_str(&format!(
+                "        if (harnessFirst <= {0} && {0} < harnessLast) {{\n",
+                index
+            ));
+            full.push_str("            long start = System.nanoTime();\n");
+            full.push_str(&format!(
+                "            var result = solution.{}({});\n",
+                spec.function, args
+            ));
+            full.push_str("            double timeMs = (System.nanoTime() - start) / 1e6;\n");
+            full.push_str(&format!(
+                "            out.printf(Locale.ROOT, \"{{\\\"nonce\\\":\\\"%s\\\",\\\"index\\\":{},\\\"passed\\\":%b,\\\"time_ms\\\":%.3f,\\\"output\\\":%s}}%n\", nonce, result == {}, timeMs, result);\n",
+                index, t.expected
+            ));
+            full.push_str("        }\n\n");
+        }
+
+        full.push_str("        double totalTime = (System.nanoTime() - programStart) / 1e6;\n");
+        full.push_str("        out.printf(Locale.ROOT, \"{\\\"nonce\\\":\\\"%s\\\",\\\"done\\\":true,\\\"time_ms\\\":%.3f}%n\", nonce, totalTime);\n");
+        full.push_str("        out.close();\n");
+        full.push_str("    }\n");
+        full.push('}');
+
+        Ok(full)
+    }
+}
+
+/// 拆分用户代码头部的 import 语句，返回 (import 行, 剩余代码)
+fn split_imports(code: &str) -> (Vec<&str>, &str) {
+    let mut imports = Vec::new();
+    let mut offset = 0;
+
+    for line in code.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with("import ") {
+            imports.push(trimmed);
+        } else if !trimmed.is_empty() {
+            break;
+        }
+        offset += line.len();
+    }
+
+    (imports, &code[offset..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::generate_ints;
+    use crate::model::request::TestCase;
+
+    #[test]
+    fn wraps_methods_in_solution_class() {
+        let code = "import java.util.*;\n\npublic int add(int a, int b) {\n    return a + b;\n}";
+        let cases = vec![TestCase::ints(&[1, 2], 3)];
+
+        let generated = generate_ints(&JavaCodeGenerator, code, "add", &cases).unwrap();
+
+        assert!(generated.contains(
+            "import java.util.Locale;\nimport java.util.*;\n\nclass Solution {\npublic int add("
+        ));
+        assert!(generated.contains("var result = solution.add(1, 2);"));
+        assert!(generated.contains("\n\npublic class Main {\n"));
+    }
+
+    #[test]
+    fn keeps_existing_solution_class() {
+        let code = "class Solution {\n    int f() { return 1; }\n}";
+
+        let generated = generate_ints(&JavaCodeGenerator, code, "f", &[]).unwrap();
+
+        assert_eq!(generated.matches("class Solution").count(), 1);
+        assert!(
+            generated.contains(
+                "\nclass Solution {\n    int f() { return 1; }\n}\n\npublic class Main {"
+            )
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use crate::codegen::{CodeGenerator, HarnessSpec, ensure_int_signature, ensure_no_benchmark};
+use anyhow::Result;
+
+pub struct JavaScriptCodeGenerator;
+
+impl CodeGenerator for JavaScriptCodeGenerator {
+    fn generate(&self, spec: &HarnessSpec) -> Result<String> {
+        ensure_int_signature(spec.signature, "JavaScript")?;
+        ensure_no_benchmark(spec, "JavaScript")?;
+
+        let mut full = String::with_capacity(spec.code.len() + spec.test_cases.len() * 300 + 500);
+
+        // 用户代码包进单独的函数，看不到测试代码的局部变量；调用它之前 nonce 已经读走
+        full.push_str("function harnessSolution() {\n");
+        full.push_str(spec.code);
+        full.push_str(&format!("\nreturn {};\n}}\n\n", spec.function));
+
+        full.push_str("(function main() {\n");
+        full.push_str("    const fs = require(\"fs\");\n");
+        full.push_str(
+            "    const harnessNonce = fs.readFileSync(0, \"utf8\").split(\"\\n\")[0].trim();\n",
+        );
+        full.push_str(&format!(
+            "    const {} = harnessSolution();\n",
+            spec.function
+        ));
+        full.push_str("    function writeRecord(record) {\n");
+        full.push_str("        fs.writeSync(3, JSON.stringify({ nonce: harnessNonce, ...record }) + \"\\n\");\n");
+        full.push_str("    }\n\n");
+        full.push_str(&format!(
+            "    const [harnessFirst, harnessLast] = process.argv.length > 3 ? [Number(process.argv[2]), Number(process.argv[3])] : [0, {}];\n",
+            spec.test_cases.len()
+        ));
+        full.push_str("    const programStart = process.hrtime.bigint();\n\n");
+
+        for (index, t) in spec.test_cases.iter().enumerate() {
+            let args = t
+                .input
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            full.push_str(&format!(
+                "    if (harnessFirst <= {0} && {0} < harnessLast) {{\n",
+                index
+            ));
+            full.push_str("        const start = process.hrtime.bigint();\n");
+            full.push_str(&format!(
+                "        const result = {}({});\n",
+                spec.function, args
+            ));
+            full.push_str(
+                "        const timeMs = Number(process.hrtime.bigint() - start) / 1e6;\n",
+            );
+            full.push_str(&format!(
+                "        writeRecord({{ index: {}, passed: result === {}, time_ms: timeMs, output: result }});\n",
+                index, t.expected
+            ));
+            full.push_str("    }\n\n");
+        }
+
+        full.push_str(
+            "    const totalTime = Number(process.hrtime.bigint() - programStart) / 1e6;\n",
+        );
+        full.push_str("    writeRecord({ done: true, time_ms: totalTime });\n");
+        full.push_str("})();");
+
+        Ok(full)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::generate_ints;
+    use crate::model::request::TestCase;
+
+    #[test]
+    fn generates_case_block() {
+        let cases = vec![TestCase::ints(&[1, 2], 3)];
+
+        let generated = generate_ints(
+            &JavaScriptCodeGenerator,
+            "function add(a, b) {\n    return a + b;\n}",
+            "add",
+            &cases,
+        )
+        .unwrap();
+
+        assert!(generated.starts_with(
+            "function harnessSolution() {\nfunction add(a, b) {\n    return a + b;\n}\nreturn add;\n}\n\n(function main() {\n"
+        ));
+        assert!(generated.contains(
+            "    const harnessNonce = fs.readFileSync(0, \"utf8\").split(\"\\n\")[0].trim();\n    const add = harnessSolution();\n"
+        ));
+        assert!(generated.contains("        const result = add(1, 2);\n"));
+        assert!(generated.contains(
+            "        writeRecord({ index: 0, passed: result === 3, time_ms: timeMs, output: result });\n"
+        ));
+        assert!(generated.ends_with("})();"));
+    }
+}
@@ -1,12 +1,205 @@
 pub mod c_generator;
 pub mod cpp_generator;
+pub mod go_generator;
+pub mod java_generator;
+pub mod javascript_generator;
 pub mod python_generator;
+pub mod rust_generator;
+pub mod template_generator;
 
-use crate::model::request::TestCase;
+use crate::model::comparison::Comparison;
+use crate::model::request::{BenchmarkOptions, TestCase};
+use crate::model::signature::{Signature, ValueType};
 use anyhow::Result;
 use async_trait::async_trait;
+use serde_json::Value;
+
+use c_generator::CCodeGenerator;
+use cpp_generator::CppCodeGenerator;
+use go_generator::GoCodeGenerator;
+use java_generator::JavaCodeGenerator;
+use javascript_generator::JavaScriptCodeGenerator;
+use python_generator::PythonCodeGenerator;
+use rust_generator::RustCodeGenerator;
+
+/// 测试代码写入结果的文件描述符
+///
+/// 测试代码启动后、调用用户代码前，先从标准输入读取一行 nonce，
+/// 之后把结果以 JSON 行写入该描述符，每行都带上 nonce：
+///
+/// - 每个用例：`{"nonce":"…","index":0,"passed":true,"time_ms":0.012,"output":<返回值 JSON>}`，
+///   开启基准测试时另有 `"bench":{"iterations":12800,"samples":[0.0081,…]}`，样本为每次调用的平均毫秒数
+/// - 全部结束：`{"nonce":"…","done":true,"time_ms":1.234}`
+///
+/// nonce 不写进源码，并在用户代码执行前读走，只保存在用户代码无法按名字引用的位置：
+///
+/// - C / C++ 在 `.preinit_array` 中读取（见 [`C_NONCE_READER`]）
+/// - Go 在 `main` 中读入局部变量，不生成包级标识符；但用户代码的 `init` 与包级变量初始化先于 `main` 执行，
+///   仍能抢先读走标准输入
+/// - JavaScript 在测试代码的函数中读取，用户代码包在另一个函数里，作用域中看不到它
+/// - Python 在函数内读取后才在单独的命名空间中执行用户代码；Python 可以通过 `sys._getframe` 等自省
+///   读到调用栈上的局部变量，这里只保证用户代码不能直接引用，防不住刻意的自省
+///
+/// 这些措施都在同一个进程内，挡住的是误用和简单的伪造，不是针对恶意代码的隔离。
+/// 标准输出和标准错误完全留给用户代码。
+/// 同一用例出现多条记录、或出现本批没有运行的用例的记录，都视为结果被篡改，见
+/// [`crate::parser::parse_output::collect_records`]。
+///
+/// 测试代码接受两个可选的命令行参数 `FIRST LAST`，只运行下标在 `[FIRST, LAST)` 内的用例，
+/// 执行器借此让每批用例在单独的进程中运行。
+pub const RESULTS_FD: i32 = 3;
+
+/// C / C++ 测试代码读取 nonce 的函数，放在用户代码之后，用户代码看不到 harness_nonce
+///
+/// `.preinit_array` 中的函数先于所有构造函数运行，用户代码中的 `__attribute__((constructor))`
+/// 和全局对象执行时，标准输入中的 nonce 已经被读走。逐字节调用 `read` 而不经过 stdio，
+/// nonce 不会留在 `stdin` 的缓冲区里被用户代码翻出来。
+pub const C_NONCE_READER: &str = r#"#include <unistd.h>
+static char harness_nonce[64];
+static void harness_read_nonce(void) {
+    size_t len = 0;
+    char c;
+    while (len + 1 < sizeof(harness_nonce) && read(0, &c, 1) == 1 && c != '\n') harness_nonce[len++] = c;
+    harness_nonce[len] = '\0';
+}
+__attribute__((section(".preinit_array"), used)) static void (*harness_preinit)(void) = harness_read_nonce;
+
+"#;
+
+/// 生成测试代码所需的全部输入
+pub struct HarnessSpec<'a> {
+    pub code: &'a str,
+    pub function: &'a str,
+    pub signature: &'a Signature,
+    pub comparison: &'a Comparison,
+    pub test_cases: &'a [TestCase],
+    /// 基准测试，None 时每个用例只调用一次
+    pub benchmark: Option<&'a BenchmarkOptions>,
+}
+
+/// 基准测试按预热估出的单次耗时分批，使测量阶段大约得到这么多个样本
+pub const BENCH_TARGET_SAMPLES: u32 = 50;
+
+/// 基准测试每个用例最多记录的样本数
+pub const BENCH_MAX_SAMPLES: u32 = 1000;
+
+/// 基准测试每批调用前准备的参数副本估计占用的字节上限
+pub const BENCH_COPY_BYTES: u64 = 8 * 1024 * 1024;
+
+/// 基准测试每批最多调用的次数
+pub const BENCH_MAX_BATCH: u64 = 1 << 30;
+
+/// 基准测试每批最多调用的次数
+///
+/// 函数可能改写参数，每次调用都使用单独的副本，副本在计时之外准备；
+/// copied 为需要复制的参数值，全部副本估计不超过 BENCH_COPY_BYTES。
+pub fn bench_max_batch<'v>(copied: impl IntoIterator<Item = &'v Value>) -> u64 {
+    let bytes: u64 = copied.into_iter().map(estimated_bytes).sum();
+    if bytes == 0 {
+        return BENCH_MAX_BATCH;
+    }
+    (BENCH_COPY_BYTES / bytes).clamp(1, BENCH_MAX_BATCH)
+}
+
+/// 值大约占用的字节数：标量 8 字节，数组与字符串另加 16 字节
+fn estimated_bytes(value: &Value) -> u64 {
+    match value {
+        Value::Array(items) => 16 + items.iter().map(estimated_bytes).sum::<u64>(),
+        Value::String(s) => 16 + s.len() as u64,
+        _ => 8,
+    }
+}
 
 #[async_trait]
 pub trait CodeGenerator: Send + Sync {
-    fn generate(&self, code: &str, function: &str, test_cases: &[TestCase]) -> Result<String>;
-}
\ No newline at end of file
+    fn generate(&self, spec: &HarnessSpec) -> Result<String>;
+}
+
+/// 根据语言 id 返回内置的代码生成器
+pub fn generator_for(lang: &str) -> Option<Box<dyn CodeGenerator>> {
+    let generator: Box<dyn CodeGenerator> = match lang {
+        "c" => Box::new(CCodeGenerator),
+        "cpp" => Box::new(CppCodeGenerator),
+        "python" => Box::new(PythonCodeGenerator),
+        "rust" => Box::new(RustCodeGenerator),
+        "go" => Box::new(GoCodeGenerator),
+        "java" => Box::new(JavaCodeGenerator),
+        "javascript" => Box::new(JavaScriptCodeGenerator),
+        _ => return None,
+    };
+    Some(generator)
+}
+
+/// 参数的展示文本，每个参数为紧凑 JSON，以 ", " 分隔
+pub fn display_args(input: &[Value]) -> String {
+    input
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 转义为 C / C++ 字符串字面量（含双引号）
+pub fn c_string_literal(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            // 八进制转义最多 3 位，不会吞掉后续字符
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\{:03o}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// 不支持基准测试的生成器调用
+pub fn ensure_no_benchmark(spec: &HarnessSpec, lang: &str) -> Result<()> {
+    if spec.benchmark.is_some() {
+        anyhow::bail!("{} 暂不支持基准测试", lang);
+    }
+    Ok(())
+}
+
+/// C / C++ 的基准测试不支持链表或二叉树参数：函数可能改写或释放节点，
+/// 为每次调用重建的节点无法安全回收
+pub fn ensure_bench_args_copyable(spec: &HarnessSpec, lang: &str) -> Result<()> {
+    if spec.benchmark.is_some() && spec.signature.params.iter().any(ValueType::is_node) {
+        anyhow::bail!("{} 的基准测试不支持链表或二叉树参数", lang);
+    }
+    Ok(())
+}
+
+/// 仅支持 int 签名的生成器调用，其它类型直接报错
+pub fn ensure_int_signature(signature: &Signature, lang: &str) -> Result<()> {
+    if signature.is_all_int() {
+        Ok(())
+    } else {
+        anyhow::bail!("{} 暂只支持 int 类型的参数与返回值", lang)
+    }
+}
+
+/// 测试辅助：按全 int 签名生成代码
+#[cfg(test)]
+pub fn generate_ints(
+    generator: &dyn CodeGenerator,
+    code: &str,
+    function: &str,
+    test_cases: &[TestCase],
+) -> Result<String> {
+    let signature = Signature::all_int(test_cases.first().map_or(0, |t| t.input.len()));
+    generator.generate(&HarnessSpec {
+        code,
+        function,
+        signature: &signature,
+        comparison: &Comparison::Exact,
+        test_cases,
+        benchmark: None,
+    })
+}
@@ -1,53 +1,488 @@
-use crate::codegen::CodeGenerator;
-use crate::model::request::TestCase;
+use crate::codegen::{
+    BENCH_MAX_SAMPLES, BENCH_TARGET_SAMPLES, CodeGenerator, HarnessSpec, bench_max_batch,
+};
+use crate::model::comparison::Comparison;
+use crate::model::request::BenchmarkOptions;
+use crate::model::signature::ValueType;
 use anyhow::Result;
+use serde_json::Value;
 
 pub struct PythonCodeGenerator;
 
 impl CodeGenerator for PythonCodeGenerator {
-    fn generate(&self, code: &str, function: &str, test_cases: &[TestCase]) -> Result<String> {
-        let mut full = String::with_capacity(code.len() + test_cases.len() * 200 + 200);
+    fn generate(&self, spec: &HarnessSpec) -> Result<String> {
+        let signature = spec.signature;
+        let mut full = String::with_capacity(spec.code.len() + spec.test_cases.len() * 250 + 300);
 
-        full.push_str("import time\n");
-        full.push_str("import resource\n\n");
-        full.push_str(code);
-        full.push_str("\n\n");
+        full.push_str("import json\n");
+        full.push_str("import os\n");
+        full.push_str("import sys\n");
+        full.push_str("import time\n\n");
+        // 用户代码可以使用的定义，执行时放进它的命名空间
+        let mut provided = Vec::new();
+        let uses_list = signature.uses(&ValueType::List);
+        let uses_tree = signature.uses(&ValueType::Tree);
+        if uses_list || uses_tree {
+            full.push_str("from typing import List, Optional\n\n");
+            provided.extend(["List", "Optional"]);
+        }
+        if uses_list {
+            full.push_str(LIST_PRELUDE);
+            provided.push("ListNode");
+        }
+        if uses_tree {
+            full.push_str(TREE_PRELUDE);
+            provided.push("TreeNode");
+        }
 
-        full.push_str("def get_memory_kb():\n");
-        full.push_str("    return resource.getrusage(resource.RUSAGE_SELF).ru_maxrss\n\n");
+        push_checker(&mut full, spec.comparison);
+        full.push_str(FINITE_PRELUDE);
 
-        full.push_str("if __name__ == '__main__':\n");
-        full.push_str("    program_start = time.time()\n");
-        full.push_str("    start_mem = get_memory_kb()\n\n");
+        // 用户代码以字符串形式保存，读取 nonce 之后才执行
+        full.push_str(&format!(
+            "harness_source = {}\n\n",
+            python_string(spec.code)
+        ));
+        full.push_str("def harness_main():\n");
+        // nonce 与结果通道只保存在局部变量中；fd 3 换成新的描述符后关闭
+        full.push_str("    harness_nonce = sys.stdin.readline().strip()\n");
+        full.push_str("    harness_out = os.fdopen(os.dup(3), 'w')\n");
+        full.push_str("    os.close(3)\n");
+        // 用户代码在单独的命名空间中执行，看不到测试代码的全局变量
+        let mut namespace = String::from("'__name__': 'solution'");
+        for name in &provided {
+            namespace.push_str(&format!(", '{0}': {0}", name));
+        }
+        full.push_str(&format!("    harness_solution = {{{}}}\n", namespace));
+        full.push_str(
+            "    exec(compile(harness_source, '<solution>', 'exec'), harness_solution)\n",
+        );
+        full.push_str(&format!(
+            "    {0} = harness_solution['{0}']\n",
+            spec.function
+        ));
+        full.push_str(&format!(
+            "    harness_first, harness_last = (int(sys.argv[1]), int(sys.argv[2])) if len(sys.argv) > 2 else (0, {})\n",
+            spec.test_cases.len()
+        ));
+        full.push_str("    program_start = time.perf_counter()\n\n");
 
-        for t in test_cases {
-            let args = t
-                .input
+        for (index, t) in spec.test_cases.iter().enumerate() {
+            let expected = signature.returns.canonical(&t.expected);
+            let args = signature
+                .params
                 .iter()
-                .map(|x| x.to_string())
+                .zip(&t.input)
+                .map(|(ty, value)| match ty {
+                    ValueType::List => format!("build_list({})", python_literal(value)),
+                    ValueType::Tree => format!("build_tree({})", python_literal(value)),
+                    _ => python_literal(value),
+                })
                 .collect::<Vec<_>>()
                 .join(", ");
-            full.push_str("    start = time.time()\n");
-            full.push_str(&format!("    result = {}({})\n", function, args));
-            full.push_str("    end = time.time()\n");
-            full.push_str("    time_ms = (end - start) * 1000\n");
             full.push_str(&format!(
-                "    status = '✅ PASS' if result == {} else '❌ FAIL'\n",
-                t.expected
+                "    if harness_first <= {} < harness_last:\n",
+                index
+            ));
+            full.push_str(&format!(
+                "        expected = {}\n",
+                python_literal(&expected)
             ));
+            full.push_str("        start = time.perf_counter()\n");
+            full.push_str(&format!("        result = {}({})\n", spec.function, args));
+            full.push_str("        end = time.perf_counter()\n");
+            full.push_str("        time_ms = (end - start) * 1000\n");
+            // 链表和二叉树返回值先序列化为层序数组
+            match signature.returns {
+                ValueType::List => full.push_str("        result = list_to_array(result)\n"),
+                ValueType::Tree => full.push_str("        result = tree_to_array(result)\n"),
+                _ => {}
+            }
             full.push_str(&format!(
-                "    print(f\"Input: ({}) => Output: {{result}} | Expected: {} | {{status}} | Time: {{time_ms:.3f}}ms\")\n\n",
-                args, t.expected
+                "        passed = bool({})\n",
+                check_expr(spec.comparison)
             ));
+            let bench = match spec.benchmark {
+                Some(benchmark) => {
+                    // 数组、链表与二叉树可能被函数改写，需要为每次调用重新构造
+                    let copied = signature
+                        .params
+                        .iter()
+                        .zip(&t.input)
+                        .filter(|(ty, _)| !ty.is_scalar() || ty.is_node())
+                        .map(|(_, value)| value);
+                    push_bench(
+                        &mut full,
+                        benchmark,
+                        spec.function,
+                        &args,
+                        bench_max_batch(copied),
+                    );
+                    ", 'bench': {'iterations': bench_iterations, 'samples': bench_samples}"
+                }
+                None => "",
+            };
+            full.push_str(&format!(
+                "        harness_out.write(json.dumps(harness_finite({{'nonce': harness_nonce, 'index': {}, 'passed': passed, 'time_ms': time_ms, 'output': result{}}}), ensure_ascii=False, allow_nan=False, default=str) + '\\n')\n",
+                index, bench
+            ));
+            full.push_str("        harness_out.flush()\n\n");
         }
 
-        full.push_str("    program_end = time.time()\n");
-        full.push_str("    end_mem = get_memory_kb()\n");
+        full.push_str("    program_end = time.perf_counter()\n");
         full.push_str("    total_time = (program_end - program_start) * 1000\n");
-        full.push_str("    print(\"\\n=== Performance ===\")\n");
-        full.push_str("    print(f\"Time: {total_time:.3f}ms\")\n");
-        full.push_str("    print(f\"Memory: {end_mem} KB\")\n");
+        full.push_str("    harness_out.write(json.dumps({'nonce': harness_nonce, 'done': True, 'time_ms': total_time}) + '\\n')\n");
+        full.push_str("    harness_out.close()\n\n");
+        full.push_str("if __name__ == '__main__':\n");
+        full.push_str("    harness_main()\n");
 
         Ok(full)
     }
-}
\ No newline at end of file
+}
+
+/// 基准测试：预热时每轮调用次数翻倍，直到用满 warmup_ms，由此估出单次耗时；
+/// 之后按估出的单次耗时分批调用，直到测量时间达到 min_time_ms，每批的平均耗时为一个样本
+///
+/// 函数可能改写参数，每批调用前在计时之外为每次调用重新构造一份参数，
+/// 每批最多调用 max_batch 次。
+fn push_bench(
+    out: &mut String,
+    benchmark: &BenchmarkOptions,
+    function: &str,
+    args: &str,
+    max_batch: u64,
+) {
+    let call_args = if args.is_empty() {
+        String::new()
+    } else {
+        format!("{},", args)
+    };
+    let mut batch = format!(
+        "            bench_args = [({}) for _ in range(bench_batch)]\n",
+        call_args
+    );
+    batch.push_str("            bench_sample_start = time.perf_counter()\n");
+    batch.push_str("            for bench_call in bench_args:\n");
+    batch.push_str(&format!("                {}(*bench_call)\n", function));
+    batch.push_str(
+        "            bench_sample_ms = (time.perf_counter() - bench_sample_start) * 1000\n",
+    );
+    out.push_str("        bench_samples = []\n");
+    out.push_str("        bench_iterations = bench_calls = 0\n");
+    out.push_str("        bench_elapsed = 0.0\n");
+    out.push_str("        bench_batch = 1\n");
+    out.push_str("        bench_start = time.perf_counter()\n");
+    out.push_str("        while True:\n");
+    out.push_str(&batch);
+    out.push_str("            bench_elapsed += bench_sample_ms\n");
+    out.push_str("            bench_calls += bench_batch\n");
+    out.push_str(&format!(
+        "            bench_batch = min(bench_batch * 2, {})\n",
+        max_batch
+    ));
+    out.push_str(&format!(
+        "            if (time.perf_counter() - bench_start) * 1000 >= {:?}:\n",
+        benchmark.warmup_ms
+    ));
+    out.push_str("                break\n");
+    out.push_str("        bench_per_call = max(bench_elapsed / bench_calls, 1e-6)\n");
+    out.push_str(&format!(
+        "        bench_batch = min(max(1, int({:?} / {} / bench_per_call)), {})\n",
+        benchmark.min_time_ms, BENCH_TARGET_SAMPLES, max_batch
+    ));
+    out.push_str("        bench_start = time.perf_counter()\n");
+    out.push_str(&format!(
+        "        while len(bench_samples) < {} and (not bench_samples or (time.perf_counter() - bench_start) * 1000 < {:?}):\n",
+        BENCH_MAX_SAMPLES, benchmark.min_time_ms
+    ));
+    out.push_str(&batch);
+    out.push_str("            bench_samples.append(bench_sample_ms / bench_batch)\n");
+    out.push_str("            bench_iterations += bench_batch\n");
+    out.push_str("        del bench_args\n");
+}
+
+/// 非精确比较时输出 compare_result(result, expected) 函数
+fn push_checker(out: &mut String, comparison: &Comparison) {
+    match comparison {
+        Comparison::Exact => {}
+        Comparison::Epsilon { abs, rel } => {
+            out.push_str("def compare_result(a, b):\n");
+            out.push_str("    if isinstance(a, (list, tuple)) and isinstance(b, (list, tuple)):\n");
+            out.push_str("        return len(a) == len(b) and all(compare_result(x, y) for x, y in zip(a, b))\n");
+            out.push_str("    if not isinstance(a, (int, float)) or isinstance(a, bool):\n");
+            out.push_str("        return False\n");
+            out.push_str(&format!(
+                "    return abs(a - b) <= {:?} or abs(a - b) <= {:?} * abs(b)\n\n",
+                abs, rel
+            ));
+        }
+        // 元素转为 JSON 文本后排序，嵌套数组也能比较
+        Comparison::Unordered => {
+            out.push_str("def compare_result(a, b):\n");
+            out.push_str("    key = lambda v: sorted(json.dumps(x, sort_keys=True) for x in v)\n");
+            out.push_str("    return isinstance(a, (list, tuple)) and key(a) == key(b)\n\n");
+        }
+        Comparison::Set => {
+            out.push_str("def compare_result(a, b):\n");
+            out.push_str("    key = lambda v: set(json.dumps(x, sort_keys=True) for x in v)\n");
+            out.push_str("    return isinstance(a, (list, tuple, set)) and key(a) == key(b)\n\n");
+        }
+        Comparison::CaseInsensitive => {
+            out.push_str("def compare_result(a, b):\n");
+            out.push_str("    return isinstance(a, str) and a.casefold() == b.casefold()\n\n");
+        }
+    }
+}
+
+fn check_expr(comparison: &Comparison) -> &'static str {
+    match comparison {
+        Comparison::Exact => "result == expected",
+        _ => "compare_result(result, expected)",
+    }
+}
+
+/// JSON 值转为 Python 字面量，数组转为 list
+fn python_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "None".to_string(),
+        Value::Bool(true) => "True".to_string(),
+        Value::Bool(false) => "False".to_string(),
+        Value::String(s) => python_string(s),
+        Value::Array(items) => format!(
+            "[{}]",
+            items
+                .iter()
+                .map(python_literal)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        _ => value.to_string(),
+    }
+}
+
+/// JSON 字符串的转义规则是 Python 字符串字面量的子集
+fn python_string(s: &str) -> String {
+    Value::String(s.to_string()).to_string()
+}
+
+/// nan / inf 不是合法的 JSON，与 C / C++ 一样输出为字符串
+const FINITE_PRELUDE: &str = r#"def harness_finite(value):
+    if isinstance(value, float) and value != value:
+        return 'NaN'
+    if isinstance(value, float) and value in (float('inf'), float('-inf')):
+        return 'Infinity' if value > 0 else '-Infinity'
+    if isinstance(value, (list, tuple)):
+        return [harness_finite(v) for v in value]
+    if isinstance(value, dict):
+        return {k: harness_finite(v) for k, v in value.items()}
+    return value
+
+"#;
+
+/// LeetCode 的链表定义，以及与数组互相转换的函数
+const LIST_PRELUDE: &str = r#"class ListNode:
+    def __init__(self, val=0, next=None):
+        self.val = val
+        self.next = next
+
+def build_list(vals):
+    head = None
+    for v in reversed(vals):
+        head = ListNode(v, head)
+    return head
+
+def list_to_array(head):
+    vals = []
+    while head:
+        vals.append(head.val)
+        head = head.next
+    return vals
+
+"#;
+
+/// LeetCode 的二叉树定义，层序数组中的 None 表示空节点
+const TREE_PRELUDE: &str = r#"class TreeNode:
+    def __init__(self, val=0, left=None, right=None):
+        self.val = val
+        self.left = left
+        self.right = right
+
+def build_tree(vals):
+    if not vals or vals[0] is None:
+        return None
+    nodes = [None if v is None else TreeNode(v) for v in vals]
+    child = 1
+    for node in nodes:
+        if child >= len(nodes):
+            break
+        if node is None:
+            continue
+        node.left = nodes[child]
+        child += 1
+        if child < len(nodes):
+            node.right = nodes[child]
+            child += 1
+    return nodes[0]
+
+def tree_to_array(root):
+    queue = [root] if root else []
+    i = 0
+    while i < len(queue):
+        if queue[i]:
+            queue.append(queue[i].left)
+            queue.append(queue[i].right)
+        i += 1
+    while queue and queue[-1] is None:
+        queue.pop()
+    return [None if node is None else node.val for node in queue]
+
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::request::TestCase;
+    use crate::model::signature::Signature;
+    use serde_json::json;
+
+    fn generate(signature: &Signature, comparison: &Comparison, test_cases: &[TestCase]) -> String {
+        PythonCodeGenerator
+            .generate(&HarnessSpec {
+                code: "",
+                function: "f",
+                signature,
+                comparison,
+                test_cases,
+                benchmark: None,
+            })
+            .unwrap()
+    }
+
+    /// 参数与返回值同为 ty 的单用例，只关心比较代码
+    fn generate_identity(ty: &str, value: Value, comparison: &Comparison) -> String {
+        let ty = ValueType::try_from(ty.to_string()).unwrap();
+        let signature = Signature {
+            params: vec![ty.clone()],
+            returns: ty,
+        };
+        let cases = vec![TestCase {
+            input: vec![value.clone()],
+            expected: value,
+            size: None,
+        }];
+        generate(&signature, comparison, &cases)
+    }
+
+    #[test]
+    fn builds_tree_with_nulls_and_empty_list() {
+        let signature = Signature {
+            params: vec![ValueType::Tree, ValueType::List],
+            returns: ValueType::Tree,
+        };
+        let cases = vec![TestCase {
+            input: vec![json!([1, null, 2]), json!([])],
+            expected: json!([1, null, 2, null]),
+            size: None,
+        }];
+
+        let generated = generate(&signature, &Comparison::Exact, &cases);
+        assert!(generated.contains("def build_list(vals):\n"));
+        assert!(generated.contains("def list_to_array(head):\n"));
+        assert!(generated.contains("def build_tree(vals):\n"));
+        assert!(generated.contains("def tree_to_array(root):\n"));
+        assert!(generated.contains("expected = [1, None, 2]\n"));
+        assert!(generated.contains("result = f(build_tree([1, None, 2]), build_list([]))\n"));
+        assert!(generated.contains("result = tree_to_array(result)\n"));
+        assert!(generated.contains("passed = bool(result == expected)\n"));
+    }
+
+    #[test]
+    fn emits_checker_for_each_comparison() {
+        let epsilon = Comparison::Epsilon {
+            abs: 1e-6,
+            rel: 0.001,
+        };
+        let generated = generate_identity("double[]", json!([1.0, 2.5]), &epsilon);
+        assert!(generated.contains(
+            "        return len(a) == len(b) and all(compare_result(x, y) for x, y in zip(a, b))\n"
+        ));
+        assert!(
+            generated.contains("    return abs(a - b) <= 1e-6 or abs(a - b) <= 0.001 * abs(b)\n")
+        );
+        assert!(generated.contains("        passed = bool(compare_result(result, expected))\n"));
+
+        let generated = generate_identity("int[]", json!([3, 1, 2]), &Comparison::Unordered);
+        assert!(
+            generated
+                .contains("    key = lambda v: sorted(json.dumps(x, sort_keys=True) for x in v)\n")
+        );
+
+        let generated = generate_identity("string[]", json!(["b", "a"]), &Comparison::Set);
+        assert!(
+            generated
+                .contains("    key = lambda v: set(json.dumps(x, sort_keys=True) for x in v)\n")
+        );
+
+        let generated = generate_identity("string", json!("Hello"), &Comparison::CaseInsensitive);
+        assert!(
+            generated.contains("    return isinstance(a, str) and a.casefold() == b.casefold()\n")
+        );
+
+        let generated = generate_identity("int", json!(1), &Comparison::Exact);
+        assert!(!generated.contains("compare_result"));
+        assert!(generated.contains("        passed = bool(result == expected)\n"));
+    }
+
+    #[test]
+    fn bench_rebuilds_args_outside_timed_region() {
+        let signature = Signature {
+            params: vec![
+                ValueType::try_from("int[]".to_string()).unwrap(),
+                ValueType::Tree,
+            ],
+            returns: ValueType::Int,
+        };
+        let cases = vec![TestCase {
+            input: vec![json!([3, 1, 2]), json!([1, null, 2])],
+            expected: json!(1),
+            size: None,
+        }];
+        let code = PythonCodeGenerator
+            .generate(&HarnessSpec {
+                code: "",
+                function: "f",
+                signature: &signature,
+                comparison: &Comparison::Exact,
+                test_cases: &cases,
+                benchmark: Some(&BenchmarkOptions {
+                    warmup_ms: 20.0,
+                    min_time_ms: 100.0,
+                }),
+            })
+            .unwrap();
+
+        // 每次调用都有自己的参数，构造在计时之前
+        let fill = code
+            .find("            bench_args = [([3, 1, 2], build_tree([1, None, 2]),) for _ in range(bench_batch)]\n            bench_sample_start = time.perf_counter()\n")
+            .unwrap();
+        let call = code
+            .find("            for bench_call in bench_args:\n                f(*bench_call)\n")
+            .unwrap();
+        assert!(fill < call);
+        assert!(code.contains(&format!(
+            "            bench_batch = min(bench_batch * 2, {})\n",
+            crate::codegen::bench_max_batch([&json!([3, 1, 2]), &json!([1, null, 2])])
+        )));
+    }
+
+    #[test]
+    fn writes_non_finite_floats_as_strings() {
+        let code = generate_identity("double[]", json!([1.5]), &Comparison::Exact);
+        assert!(code.contains("def harness_finite(value):\n"));
+        assert!(code.contains("        return 'Infinity' if value > 0 else '-Infinity'\n"));
+        assert!(code.contains(
+            "harness_out.write(json.dumps(harness_finite({'nonce': harness_nonce, 'index': 0,"
+        ));
+        assert!(code.contains("ensure_ascii=False, allow_nan=False, default=str)"));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::codegen::{CodeGenerator, HarnessSpec, ensure_int_signature, ensure_no_benchmark};
+use anyhow::Result;
+
+pub struct RustCodeGenerator;
+
+impl CodeGenerator for RustCodeGenerator {
+    fn generate(&self, spec: &HarnessSpec) -> Result<String> {
+        ensure_int_signature(spec.signature, "Rust")?;
+        ensure_no_benchmark(spec, "Rust")?;
+
+        let mut full = String::with_capacity(spec.code.len() + spec.test_cases.len() * 350 + 600);
+
+        // 不使用 use 导入，避免与用户代码中的 use 语句冲突
+        full.push_str(spec.code);
+        full.push_str("\n\n");
+
+        full.push_str("fn main() {\n");
+        full.push_str("    let mut harness_nonce = String::new();\n");
+        full.push_str("    std::io::stdin().read_line(&mut harness_nonce).ok();\n");
+        full.push_str("    let harness_nonce = harness_nonce.trim();\n");
+        full.push_str("    let mut harness_out = unsafe { <std::fs::File as std::os::fd::FromRawFd>::from_raw_fd(3) };\n");
+        full.push_str("    let harness_args: Vec<usize> = std::env::args().skip(1).filter_map(|arg| arg.parse().ok()).collect();\n");
+        full.push_str(&format!(
+            "    let (harness_first, harness_last) = if harness_args.len() > 1 {{ (harness_args[0], harness_args[1]) }} else {{ (0, {}) }};\n",
+            spec.test_cases.len()
+        ));
+        full.push_str("    let program_start = std::time::Instant::now();\n\n");
+
+        for (index, t) in spec.test_cases.iter().enumerate() {
+            let args = t
+                .input
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            full.push_str(&format!(
+                "    if harness_first <= {0} && {0} < harness_last {{\n",
+                index
+            ));
+            full.push_str("        let start = std::time::Instant::now();\n");
+            full.push_str(&format!(
+                "        let result = {}({});\n",
+                spec.function, args
+            ));
+            full.push_str("        let time_ms = start.elapsed().as_secs_f64() * 1000.0;\n");
+            full.push_str(&format!(
+                "        let record = format!(\"{{{{\\\"nonce\\\":\\\"{{}}\\\",\\\"index\\\":{},\\\"passed\\\":{{}},\\\"time_ms\\\":{{:.3}},\\\"output\\\":{{}}}}}}\\n\", harness_nonce, result == {}, time_ms, result);\n",
+                index, t.expected
+            ));
+            full.push_str(
+                "        std::io::Write::write_all(&mut harness_out, record.as_bytes()).ok();\n",
+            );
+            full.push_str("    }\n\n");
+        }
+
+        full.push_str("    let total_time = program_start.elapsed().as_secs_f64() * 1000.0;\n");
+        full.push_str("    let record = format!(\"{{\\\"nonce\\\":\\\"{}\\\",\\\"done\\\":true,\\\"time_ms\\\":{:.3}}}\\n\", harness_nonce, total_time);\n");
+        full.push_str("    std::io::Write::write_all(&mut harness_out, record.as_bytes()).ok();\n");
+        full.push('}');
+
+        Ok(full)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::generate_ints;
+    use crate::model::request::TestCase;
+
+    #[test]
+    fn generates_main_harness() {
+        let cases = vec![TestCase::ints(&[1, 2], 3), TestCase::ints(&[-5, 7], 2)];
+        let code = "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}";
+
+        let generated = generate_ints(&RustCodeGenerator, code, "add", &cases).unwrap();
+
+        let expected = r#"fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+fn main() {
+    let mut harness_nonce = String::new();
+    std::io::stdin().read_line(&mut harness_nonce).ok();
+    let harness_nonce = harness_nonce.trim();
+    let mut harness_out = unsafe { <std::fs::File as std::os::fd::FromRawFd>::from_raw_fd(3) };
+    let harness_args: Vec<usize> = std::env::args().skip(1).filter_map(|arg| arg.parse().ok()).collect();
+    let (harness_first, harness_last) = if harness_args.len() > 1 { (harness_args[0], harness_args[1]) } else { (0, 2) };
+    let program_start = std::time::Instant::now();
+
+    if harness_first <= 0 && 0 < harness_last {
+        let start = std::time::Instant::now();
+        let result = add(1, 2);
+        let time_ms = start.elapsed().as_secs_f64() * 1000.0;
+        let record = format!("{{\"nonce\":\"{}\",\"index\":0,\"passed\":{},\"time_ms\":{:.3},\"output\":{}}}\n", harness_nonce, result == 3, time_ms, result);
+        std::io::Write::write_all(&mut harness_out, record.as_bytes()).ok();
+    }
+
+    if harness_first <= 1 && 1 < harness_last {
+        let start = std::time::Instant::now();
+        let result = add(-5, 7);
+        let time_ms = start.elapsed().as_secs_f64() * 1000.0;
+        let record = format!("{{\"nonce\":\"{}\",\"index\":1,\"passed\":{},\"time_ms\":{:.3},\"output\":{}}}\n", harness_nonce, result == 2, time_ms, result);
+        std::io::Write::write_all(&mut harness_out, record.as_bytes()).ok();
+    }
+
+    let total_time = program_start.elapsed().as_secs_f64() * 1000.0;
+    let record = format!("{{\"nonce\":\"{}\",\"done\":true,\"time_ms\":{:.3}}}\n", harness_nonce, total_time);
+    std::io::Write::write_all(&mut harness_out, record.as_bytes()).ok();
+}"#;
+        assert_eq!(generated, expected);
+    }
+
+    #[test]
+    fn generates_empty_case_list() {
+        let generated = generate_ints(&RustCodeGenerator, "fn f() -> i32 { 0 }", "f", &[]).unwrap();
+
+        assert!(generated.starts_with("fn f() -> i32 { 0 }\n\nfn main() {"));
+        assert!(!generated.contains("let result"));
+        assert!(
+            generated.ends_with(
+                "std::io::Write::write_all(&mut harness_out, record.as_bytes()).ok();\n}"
+            )
+        );
+    }
+}
@@ -0,0 +1,440 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use crate::codegen::{
+    CodeGenerator, HarnessSpec, display_args, ensure_int_signature, ensure_no_benchmark,
+};
+use crate::config::TemplateConfig;
+use crate::model::comparison::Comparison;
+use anyhow::Result;
+
+/// 占位符形如 `{{name}}`，只匹配小写标识符，C 的 `{{1, 2}}` 之类不会被误认
+static RE_PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{\s*([a-z_]+)\s*\}\}").unwrap());
+
+/// prelude / epilogue 可用的占位符，comparison 为比较方式的 JSON（目前总是 {"mode":"exact"}），count 为用例个数
+const GLOBAL_PLACEHOLDERS: [&str; 4] = ["code", "function", "comparison", "count"];
+/// case 可用的占位符：args 为逗号分隔的 JSON 参数，expected 为 JSON，index 从 0 开始
+const CASE_PLACEHOLDERS: [&str; 7] = [
+    "code",
+    "function",
+    "comparison",
+    "count",
+    "args",
+    "expected",
+    "index",
+];
+
+/// 由 config.toml 中 `[languages.<id>.template]` 描述的代码生成器
+///
+/// 生成结果为 prelude + 每个用例渲染一次的 case + epilogue。
+/// 模板只能写出 `==` 比较，因此仅支持 int 签名与精确比较，其它请求直接报错。
+pub struct TemplateCodeGenerator {
+    template: TemplateConfig,
+}
+
+impl TemplateCodeGenerator {
+    pub fn new(template: TemplateConfig) -> Result<Self> {
+        check_placeholders("prelude", &template.prelude, &GLOBAL_PLACEHOLDERS)?;
+        check_placeholders("case", &template.case, &CASE_PLACEHOLDERS)?;
+        check_placeholders("epilogue", &template.epilogue, &GLOBAL_PLACEHOLDERS)?;
+        Ok(Self { template })
+    }
+}
+
+impl CodeGenerator for TemplateCodeGenerator {
+    fn generate(&self, spec: &HarnessSpec) -> Result<String> {
+        ensure_no_benchmark(spec, "模板生成器")?;
+        ensure_int_signature(spec.signature, "模板生成器")?;
+        if *spec.comparison != Comparison::Exact {
+            anyhow::bail!(
+                "模板生成器暂只支持精确比较，不支持 {}",
+                spec.comparison.name()
+            );
+        }
+        let (code, function, test_cases) = (spec.code, spec.function, spec.test_cases);
+        let mut full = String::with_capacity(
+            code.len()
+                + self.template.prelude.len()
+                + self.template.epilogue.len()
+                + test_cases.len() * (self.template.case.len() + 50),
+        );
+        let comparison = serde_json::to_string(spec.comparison)?;
+        let count = test_cases.len().to_string();
+        let globals = [
+            ("code", code),
+            ("function", function),
+            ("comparison", comparison.as_str()),
+            ("count", count.as_str()),
+        ];
+
+        full.push_str(&render(&self.template.prelude, &globals));
+
+        for (index, t) in test_cases.iter().enumerate() {
+            let args = display_args(&t.input);
+            let expected = t.expected.to_string();
+            let index = index.to_string();
+            let vars = [
+                ("code", code),
+                ("function", function),
+                ("comparison", comparison.as_str()),
+                ("count", count.as_str()),
+                ("args", args.as_str()),
+                ("expected", expected.as_str()),
+                ("index", index.as_str()),
+            ];
+            full.push_str(&render(&self.template.case, &vars));
+        }
+
+        full.push_str(&render(&self.template.epilogue, &globals));
+
+        Ok(full)
+    }
+}
+
+/// 一次性替换所有占位符，替换进来的用户代码不会被再次展开
+fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    RE_PLACEHOLDER
+        .replace_all(template, |cap: &Captures| {
+            vars.iter()
+                .find(|(name, _)| *name == &cap[1])
+                .map(|(_, value)| value.to_string())
+                .unwrap_or_else(|| cap[0].to_string())
+        })
+        .into_owned()
+}
+
+fn check_placeholders(section: &str, template: &str, allowed: &[&str]) -> Result<()> {
+    for cap in RE_PLACEHOLDER.captures_iter(template) {
+        if !allowed.contains(&&cap[1]) {
+            anyhow::bail!(
+                "模板 {} 中的占位符 {{{{{}}}}} 不可用，可用占位符: {}",
+                section,
+                &cap[1],
+                allowed.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::c_generator::CCodeGenerator;
+    use crate::codegen::cpp_generator::CppCodeGenerator;
+    use crate::codegen::generate_ints;
+    use crate::codegen::python_generator::PythonCodeGenerator;
+    use crate::model::request::TestCase;
+    use crate::model::signature::{Signature, ValueType};
+
+    fn template(prelude: &str, case: &str, epilogue: &str) -> TemplateCodeGenerator {
+        TemplateCodeGenerator::new(TemplateConfig {
+            prelude: prelude.to_string(),
+            case: case.to_string(),
+            epilogue: epilogue.to_string(),
+        })
+        .unwrap()
+    }
+
+    fn sample_cases() -> Vec<TestCase> {
+        vec![TestCase::ints(&[1, 2], 3), TestCase::ints(&[-4, 10], 7)]
+    }
+
+    #[test]
+    fn c_generator_as_template() {
+        let generator = template(
+            r#"#include <stdio.h>
+#include <stdbool.h>
+#include <string.h>
+#include <time.h>
+
+{{code}}
+
+#include <unistd.h>
+static char harness_nonce[64];
+static void harness_read_nonce(void) {
+    size_t len = 0;
+    char c;
+    while (len + 1 < sizeof(harness_nonce) && read(0, &c, 1) == 1 && c != '\n') harness_nonce[len++] = c;
+    harness_nonce[len] = '\0';
+}
+__attribute__((section(".preinit_array"), used)) static void (*harness_preinit)(void) = harness_read_nonce;
+
+static double harness_now_ms(void) {
+    struct timespec ts;
+    clock_gettime(CLOCK_MONOTONIC, &ts);
+    return ts.tv_sec * 1000.0 + ts.tv_nsec / 1e6;
+}
+
+int main(int argc, char** argv) {
+    FILE* harness_out = fdopen(3, "w");
+    if (!harness_out) {
+        fprintf(stderr, "results fd 3 is not open\n");
+        return 1;
+    }
+    int harness_first = 0, harness_last = {{count}};
+    if (argc > 2) {
+        sscanf(argv[1], "%d", &harness_first);
+        sscanf(argv[2], "%d", &harness_last);
+    }
+    double program_start = harness_now_ms();
+
+"#,
+            r#"    if (harness_first <= {{index}} && {{index}} < harness_last) {
+        int expected = {{expected}};
+        double start = harness_now_ms();
+        int result = {{function}}({{args}});
+        double time_ms = harness_now_ms() - start;
+        int passed = result == expected;
+        fprintf(harness_out, "{\"nonce\":\"%s\",\"index\":{{index}},\"passed\":%s,\"time_ms\":%.3f,\"output\":", harness_nonce, passed ? "true" : "false", time_ms);
+        fprintf(harness_out, "%d", result);
+        fprintf(harness_out, "}\n");
+        fflush(harness_out);
+    }
+
+"#,
+            r#"    double total_time = harness_now_ms() - program_start;
+    fprintf(harness_out, "{\"nonce\":\"%s\",\"done\":true,\"time_ms\":%.3f}\n", harness_nonce, total_time);
+    fclose(harness_out);
+    return 0;
+}"#,
+        );
+        let code = "int add(int a, int b) { return a + b; }";
+        let cases = sample_cases();
+
+        assert_eq!(
+            generate_ints(&generator, code, "add", &cases).unwrap(),
+            generate_ints(&CCodeGenerator, code, "add", &cases).unwrap()
+        );
+    }
+
+    #[test]
+    fn cpp_generator_as_template() {
+        let generator = template(
+            r#"#include <iostream>
+#include <iomanip>
+#include <sstream>
+#include <cstdio>
+#include <cstring>
+#include <cmath>
+#include <string>
+#include <vector>
+#include <chrono>
+using namespace std;
+using namespace std::chrono;
+
+{{code}}
+
+#include <unistd.h>
+static char harness_nonce[64];
+static void harness_read_nonce(void) {
+    size_t len = 0;
+    char c;
+    while (len + 1 < sizeof(harness_nonce) && read(0, &c, 1) == 1 && c != '\n') harness_nonce[len++] = c;
+    harness_nonce[len] = '\0';
+}
+__attribute__((section(".preinit_array"), used)) static void (*harness_preinit)(void) = harness_read_nonce;
+
+void print_json(ostream& os, int v) { os << v; }
+void print_json(ostream& os, long v) { os << v; }
+void print_json(ostream& os, long long v) { os << v; }
+void print_json(ostream& os, double v) {
+    if (isnan(v)) os << "\"NaN\"";
+    else if (isinf(v)) os << (v > 0 ? "\"Infinity\"" : "\"-Infinity\"");
+    else os << setprecision(15) << v;
+}
+void print_json(ostream& os, bool v) { os << (v ? "true" : "false"); }
+void print_json(ostream& os, const string& s) {
+    os << '"';
+    for (unsigned char c : s) {
+        if (c == '"' || c == '\\') os << '\\' << c;
+        else if (c == '\n') os << "\\n";
+        else if (c < 0x20) os << "\\u" << hex << setw(4) << setfill('0') << (int)c << dec;
+        else os << c;
+    }
+    os << '"';
+}
+template <typename T>
+void print_json(ostream& os, const vector<T>& v) {
+    os << '[';
+    for (size_t i = 0; i < v.size(); i++) {
+        if (i) os << ',';
+        print_json(os, v[i]);
+    }
+    os << ']';
+}
+
+int main(int argc, char** argv) {
+    FILE* harness_out = fdopen(3, "w");
+    if (!harness_out) {
+        cerr << "results fd 3 is not open" << endl;
+        return 1;
+    }
+    int harness_first = argc > 2 ? stoi(argv[1]) : 0;
+    int harness_last = argc > 2 ? stoi(argv[2]) : {{count}};
+    auto program_start = steady_clock::now();
+
+"#,
+            r#"    if (harness_first <= {{index}} && {{index}} < harness_last) {
+        int expected = {{expected}};
+        auto start = steady_clock::now();
+        auto result = {{function}}({{args}});
+        auto end = steady_clock::now();
+        double time_ms = duration_cast<microseconds>(end - start).count() / 1000.0;
+        bool passed = result == expected;
+        ostringstream record;
+        record << "{\"nonce\":\"" << harness_nonce << "\",\"index\":{{index}},\"passed\":" << (passed ? "true" : "false") << ",\"time_ms\":" << time_ms << ",\"output\":";
+        print_json(record, result);
+        record << "}\n";
+        fputs(record.str().c_str(), harness_out);
+        fflush(harness_out);
+    }
+
+"#,
+            r#"    auto program_end = steady_clock::now();
+    double total_time = duration_cast<microseconds>(program_end - program_start).count() / 1000.0;
+    ostringstream record;
+    record << "{\"nonce\":\"" << harness_nonce << "\",\"done\":true,\"time_ms\":" << total_time << "}\n";
+    fputs(record.str().c_str(), harness_out);
+    fclose(harness_out);
+    return 0;
+}"#,
+        );
+        let code = "int add(int a, int b) { return a + b; }";
+        let cases = sample_cases();
+
+        assert_eq!(
+            generate_ints(&generator, code, "add", &cases).unwrap(),
+            generate_ints(&CppCodeGenerator, code, "add", &cases).unwrap()
+        );
+    }
+
+    #[test]
+    fn python_generator_as_template() {
+        let generator = template(
+            r#"import json
+import os
+import sys
+import time
+
+def harness_finite(value):
+    if isinstance(value, float) and value != value:
+        return 'NaN'
+    if isinstance(value, float) and value in (float('inf'), float('-inf')):
+        return 'Infinity' if value > 0 else '-Infinity'
+    if isinstance(value, (list, tuple)):
+        return [harness_finite(v) for v in value]
+    if isinstance(value, dict):
+        return {k: harness_finite(v) for k, v in value.items()}
+    return value
+
+harness_source = r'''{{code}}'''
+
+def harness_main():
+    harness_nonce = sys.stdin.readline().strip()
+    harness_out = os.fdopen(os.dup(3), 'w')
+    os.close(3)
+    harness_solution = {'__name__': 'solution'}
+    exec(compile(harness_source, '<solution>', 'exec'), harness_solution)
+    {{function}} = harness_solution['{{function}}']
+    harness_first, harness_last = (int(sys.argv[1]), int(sys.argv[2])) if len(sys.argv) > 2 else (0, {{count}})
+    program_start = time.perf_counter()
+
+"#,
+            r#"    if harness_first <= {{index}} < harness_last:
+        expected = {{expected}}
+        start = time.perf_counter()
+        result = {{function}}({{args}})
+        end = time.perf_counter()
+        time_ms = (end - start) * 1000
+        passed = bool(result == expected)
+        harness_out.write(json.dumps(harness_finite({'nonce': harness_nonce, 'index': {{index}}, 'passed': passed, 'time_ms': time_ms, 'output': result}), ensure_ascii=False, allow_nan=False, default=str) + '\n')
+        harness_out.flush()
+
+"#,
+            r#"    program_end = time.perf_counter()
+    total_time = (program_end - program_start) * 1000
+    harness_out.write(json.dumps({'nonce': harness_nonce, 'done': True, 'time_ms': total_time}) + '\n')
+    harness_out.close()
+
+if __name__ == '__main__':
+    harness_main()
+"#,
+        );
+        let code = "def add(a, b):\n    return a + b";
+        let cases = sample_cases();
+        // 模板只能原样嵌入用户代码，用原始字符串代替生成器中的 JSON 转义字符串
+        let expected = generate_ints(&PythonCodeGenerator, code, "add", &cases)
+            .unwrap()
+            .replace(
+                &serde_json::Value::String(code.to_string()).to_string(),
+                &format!("r'''{}'''", code),
+            );
+
+        assert_eq!(
+            generate_ints(&generator, code, "add", &cases).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn renders_index_and_keeps_user_braces() {
+        let generator = template(
+            "{{code}}\n",
+            "case {{index}}: {{ function }}({{args}})\n",
+            "",
+        );
+        let code = "int grid[1][2] = {{1, 2}}; // {{args}}";
+
+        let generated = generate_ints(&generator, code, "f", &sample_cases()).unwrap();
+
+        assert_eq!(
+            generated,
+            "int grid[1][2] = {{1, 2}}; // {{args}}\ncase 0: f(1, 2)\ncase 1: f(-4, 10)\n"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_placeholder() {
+        let result = TemplateCodeGenerator::new(TemplateConfig {
+            prelude: "{{args}}".to_string(),
+            case: String::new(),
+            epilogue: String::new(),
+        });
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_typed_signature_and_inexact_comparison() {
+        let generator = template("{{code}}\n", "{{function}}({{args}})\n", "");
+        let cases = sample_cases();
+        let spec = |signature, comparison| HarnessSpec {
+            code: "",
+            function: "f",
+            signature,
+            comparison,
+            test_cases: &cases,
+            benchmark: None,
+        };
+
+        let ints = Signature::all_int(2);
+        let doubles = Signature {
+            params: vec![ValueType::Int, ValueType::Int],
+            returns: ValueType::Double,
+        };
+        let epsilon = Comparison::Epsilon {
+            abs: 1e-6,
+            rel: 0.0,
+        };
+
+        assert!(generator.generate(&spec(&ints, &Comparison::Exact)).is_ok());
+        assert!(
+            generator
+                .generate(&spec(&doubles, &Comparison::Exact))
+                .is_err()
+        );
+        assert!(generator.generate(&spec(&doubles, &epsilon)).is_err());
+        assert!(generator.generate(&spec(&ints, &Comparison::Set)).is_err());
+    }
+}
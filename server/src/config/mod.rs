@@ -1,19 +1,64 @@
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
+use std::time::Duration;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
     pub server: ServerConfig,
     pub docker: DockerConfig,
     pub containers: HashMap<String, String>,
+    /// 临时容器使用的镜像，backend = "ephemeral" 的语言必须配置
+    #[serde(default)]
+    pub images: HashMap<String, String>,
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+    #[serde(default)]
+    pub pool: PoolConfig,
+    #[serde(default)]
+    pub compile_cache: CompileCacheConfig,
     pub languages: HashMap<String, LanguageConfig>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ServerConfig {
     pub bind_addr: String,
+    /// 语言未设置 run_timeout 时的默认运行时限（秒）
     pub run_timeout: u64,
+    /// 语言未设置 cpu_timeout 时的默认 CPU 时限（秒），都未设置时只限制墙钟时间
+    #[serde(default)]
+    pub cpu_timeout: Option<f64>,
+    /// 清理遗留运行目录的间隔（秒）
+    #[serde(default = "default_reaper_interval")]
+    pub reaper_interval: u64,
+    /// 运行目录超过该时间（秒）没有修改即视为遗留
+    #[serde(default = "default_orphan_max_age")]
+    pub orphan_max_age: u64,
+    /// 用户程序 stdout 的上限（KB），超出后终止进程
+    #[serde(default = "default_output_limit_kb")]
+    pub stdout_limit_kb: usize,
+    /// 用户程序 stderr 的上限（KB）
+    #[serde(default = "default_output_limit_kb")]
+    pub stderr_limit_kb: usize,
+    /// 运行时的内存上限（MB），请求未指定时使用，请求指定的值也不能超过它
+    #[serde(default = "default_memory_limit_mb")]
+    pub memory_limit_mb: u64,
+}
+
+fn default_reaper_interval() -> u64 {
+    60
+}
+
+fn default_orphan_max_age() -> u64 {
+    600
+}
+
+fn default_output_limit_kb() -> usize {
+    64
+}
+
+fn default_memory_limit_mb() -> u64 {
+    256
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -24,15 +69,171 @@ pub struct DockerConfig {
     pub request_timeout: u64,
 }
 
+/// 临时容器的资源限制
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct SandboxConfig {
+    pub memory_mb: i64,
+    /// 内存与 swap 之和，等于 memory_mb 时禁用 swap
+    pub memory_swap_mb: i64,
+    pub cpus: f64,
+    pub pids_limit: i64,
+    /// 挂载在 /tmp 的 tmpfs 大小
+    pub tmpfs_mb: i64,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            memory_mb: 256,
+            memory_swap_mb: 256,
+            cpus: 1.0,
+            pids_limit: 64,
+            tmpfs_mb: 64,
+        }
+    }
+}
+
+/// 临时容器预热池
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct PoolConfig {
+    /// 每个镜像保持的空闲容器数，0 表示不预热
+    pub size: usize,
+    /// 空闲容器的最长存活时间（秒），超过后删除重建
+    pub max_lifetime: u64,
+    /// 按语言设置的最少空闲容器数，大于 size 时生效
+    pub min_idle: HashMap<String, usize>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            size: 0,
+            max_lifetime: 300,
+            min_idle: HashMap::new(),
+        }
+    }
+}
+
+/// 共享容器内的编译缓存
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct CompileCacheConfig {
+    pub enabled: bool,
+    /// 缓存在容器内的目录
+    pub dir: String,
+    /// 最多保留的条目数，超出后淘汰最久未用的条目
+    pub max_entries: usize,
+}
+
+impl Default for CompileCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: "/var/cache/run_code".to_string(),
+            max_entries: 200,
+        }
+    }
+}
+
+/// 语言的执行后端
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// 在 [containers] 中配置的长期运行容器里 exec
+    #[default]
+    Shared,
+    /// 每次提交从 [images] 中的镜像创建带资源限制的临时容器，结束后删除
+    Ephemeral,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct LanguageConfig {
     pub enabled: bool,
+    #[serde(default)]
+    pub backend: Backend,
     pub file_extension: String,
+    /// 固定的源文件名（不含扩展名），如 Java 的 Main；设置后每次运行使用独立工作目录
+    #[serde(default)]
+    pub source_name: Option<String>,
     // #[serde(default)]
     // pub compiler: Option<String>,
     #[serde(default)]
     pub compile_cmd: Option<String>,
-    pub run_cmd: String
+    /// 编译产物只有 {binary} 一个文件时可以开启编译缓存
+    #[serde(default)]
+    pub compile_cache: bool,
+    /// 编译时限（秒）
+    #[serde(default = "default_compile_timeout")]
+    pub compile_timeout: f64,
+    /// 运行时限（秒），未设置时使用 [server] 的 run_timeout
+    #[serde(default)]
+    pub run_timeout: Option<f64>,
+    /// 运行的 CPU 时限（秒，用户态与内核态之和），未设置时使用 [server] 的 cpu_timeout
+    #[serde(default)]
+    pub cpu_timeout: Option<f64>,
+    /// 输出上限（KB），只能比 [server] 中的上限更小
+    #[serde(default)]
+    pub stdout_limit_kb: Option<usize>,
+    #[serde(default)]
+    pub stderr_limit_kb: Option<usize>,
+    /// 是否由启动器限制地址空间；JVM、V8 等预留大量虚拟内存的运行时应关闭，
+    /// 改为在 run_cmd 中用 {memory_mb} 设置堆上限
+    #[serde(default = "default_memory_rlimit")]
+    pub memory_rlimit: bool,
+    pub run_cmd: String,
+    /// 模板驱动的代码生成器，设置后优先于内置生成器
+    #[serde(default)]
+    pub template: Option<TemplateConfig>,
+}
+
+fn default_compile_timeout() -> f64 {
+    10.0
+}
+
+fn default_memory_rlimit() -> bool {
+    true
+}
+
+impl LanguageConfig {
+    pub fn compile_limit(&self) -> Duration {
+        Duration::from_secs_f64(self.compile_timeout)
+    }
+
+    pub fn run_limit(&self, server: &ServerConfig) -> Duration {
+        Duration::from_secs_f64(self.run_timeout.unwrap_or(server.run_timeout as f64))
+    }
+
+    pub fn cpu_limit(&self, server: &ServerConfig) -> Option<Duration> {
+        self.cpu_timeout
+            .or(server.cpu_timeout)
+            .map(Duration::from_secs_f64)
+    }
+
+    /// 启动时检查各项时限都是有限的正数；负数、NaN 与无穷大会让上面的转换在处理请求时 panic
+    pub fn check_limits(&self, server: &ServerConfig) -> anyhow::Result<()> {
+        let limits = [
+            ("compile_timeout", Some(self.compile_timeout)),
+            ("run_timeout", self.run_timeout),
+            ("cpu_timeout", self.cpu_timeout.or(server.cpu_timeout)),
+        ];
+        for (name, seconds) in limits.into_iter().filter_map(|(name, s)| Some((name, s?))) {
+            if Duration::try_from_secs_f64(seconds).map_or(true, |limit| limit.is_zero()) {
+                anyhow::bail!("{} 必须是有限的正数，当前为 {}", name, seconds);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `[languages.<id>.template]`，支持的占位符见 `codegen::template_generator`
+#[derive(Debug, Deserialize, Clone)]
+pub struct TemplateConfig {
+    pub prelude: String,
+    pub case: String,
+    #[serde(default)]
+    pub epilogue: String,
 }
 
 
@@ -43,11 +244,47 @@ impl Config {
         Ok(config)
     }
 
-    pub fn get_language(&self, lang: &str) -> Option<&LanguageConfig> {
-        self.languages.get(lang).filter(|cfg| cfg.enabled)
-    }
-
     pub fn get_container(&self, lang: &str) -> Option<&str> {
         self.containers.get(lang).map(|s| s.as_str())
     }
-}
\ No newline at end of file
+
+    pub fn get_image(&self, lang: &str) -> Option<&str> {
+        self.images.get(lang).map(|s| s.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn language(limits: &str) -> LanguageConfig {
+        toml::from_str(&format!(
+            "enabled = true\nfile_extension = \"c\"\nrun_cmd = \"./main\"\n{}",
+            limits
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn rejects_invalid_time_limits() {
+        let server: ServerConfig =
+            toml::from_str("bind_addr = \"0.0.0.0:3000\"\nrun_timeout = 5").unwrap();
+
+        assert!(language("").check_limits(&server).is_ok());
+        assert!(language("run_timeout = 1.5\ncpu_timeout = 1").check_limits(&server).is_ok());
+        for limits in [
+            "compile_timeout = -1",
+            "run_timeout = nan",
+            "cpu_timeout = inf",
+            "run_timeout = 0",
+        ] {
+            assert!(language(limits).check_limits(&server).is_err(), "{}", limits);
+        }
+
+        let server = ServerConfig {
+            cpu_timeout: Some(-2.0),
+            ..server
+        };
+        assert!(language("").check_limits(&server).is_err());
+    }
+}
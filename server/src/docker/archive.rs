@@ -0,0 +1,108 @@
+use anyhow::Result;
+use bollard::container::UploadToContainerOptions;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tar::{Builder, EntryType, Header};
+
+use crate::docker::client::get_docker;
+use crate::docker::exec::{OutputLimits, exec_in_container};
+
+/// 容器内解包的超时时间
+const EXTRACT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 把文件打包为 tar 流，通过 Docker 归档接口上传到容器中的 dir 目录
+///
+/// dir 为绝对路径，不存在时一并创建；files 中的文件名相对于 dir。
+pub async fn upload_files(container: &str, dir: &str, files: &[(&str, &[u8])]) -> Result<()> {
+    let archive = build_archive(dir, files)?;
+
+    get_docker()
+        .upload_to_container(
+            container,
+            Some(UploadToContainerOptions {
+                path: "/",
+                ..Default::default()
+            }),
+            archive.into(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// 与 upload_files 相同，但在容器内执行 tar 从标准输入解包，
+/// 用于归档接口无法写入的只读根文件系统与 tmpfs
+pub async fn extract_files(container: &str, dir: &str, files: &[(&str, &[u8])]) -> Result<()> {
+    let archive = build_archive(dir, files)?;
+
+    let output = exec_in_container(
+        container,
+        "tar -x -C / --no-same-owner",
+        Some(&archive),
+        EXTRACT_TIMEOUT,
+        OutputLimits::INTERNAL,
+    )
+    .await?;
+    if output.exit_code != Some(0) {
+        anyhow::bail!("解包文件失败: {}", output.stderr);
+    }
+
+    Ok(())
+}
+
+fn build_archive(dir: &str, files: &[(&str, &[u8])]) -> Result<Vec<u8>> {
+    let root = dir.trim_matches('/');
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut builder = Builder::new(Vec::new());
+
+    let mut header = Header::new_gnu();
+    header.set_entry_type(EntryType::Directory);
+    header.set_mode(0o755);
+    header.set_size(0);
+    header.set_mtime(mtime);
+    builder.append_data(&mut header, root, std::io::empty())?;
+
+    for (name, content) in files {
+        let mut header = Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_size(content.len() as u64);
+        header.set_mtime(mtime);
+        builder.append_data(&mut header, format!("{}/{}", root, name), *content)?;
+    }
+
+    Ok(builder.into_inner()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn archives_files_under_dir() {
+        let archive = build_archive(
+            "/tmp/main_1",
+            &[("main.c", b"int main() {}"), ("case_0.in", b"1 2\n")],
+        )
+        .unwrap();
+
+        let mut entries = Vec::new();
+        for entry in tar::Archive::new(archive.as_slice()).entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut content = String::new();
+            entry.read_to_string(&mut content).unwrap();
+            entries.push((entry.path().unwrap().display().to_string(), content));
+        }
+
+        assert_eq!(
+            entries,
+            vec![
+                ("tmp/main_1".to_string(), String::new()),
+                ("tmp/main_1/main.c".to_string(), "int main() {}".to_string()),
+                ("tmp/main_1/case_0.in".to_string(), "1 2\n".to_string()),
+            ]
+        );
+    }
+}
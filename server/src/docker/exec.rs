@@ -0,0 +1,270 @@
+use anyhow::Result;
+use bollard::container::LogOutput;
+use bollard::exec::{CreateExecOptions, StartExecResults};
+use bytes::BytesMut;
+use futures_util::StreamExt;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::time::timeout;
+use uuid::Uuid;
+
+use crate::docker::client::get_docker;
+
+/// 一次 exec 的结果
+pub struct ExecOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// 进程退出码，超时或无法获取时为 None
+    pub exit_code: Option<i64>,
+    /// 被宿主机超时中断
+    pub timed_out: bool,
+    /// stdout 超过上限，只保留了前 limits.stdout 字节，进程已被杀掉
+    pub stdout_truncated: bool,
+    /// stderr 超过上限，只保留了前 limits.stderr 字节，进程已被杀掉
+    pub stderr_truncated: bool,
+    pub elapsed: Duration,
+}
+
+impl ExecOutput {
+    pub fn truncated(&self) -> bool {
+        self.stdout_truncated || self.stderr_truncated
+    }
+}
+
+/// exec 的 stdout / stderr 各自保留的字节上限，超出后停止读取并杀掉进程
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputLimits {
+    pub stdout: usize,
+    pub stderr: usize,
+}
+
+impl OutputLimits {
+    /// 服务端自己的命令（清理、解包等）使用的上限
+    pub const INTERNAL: Self = Self {
+        stdout: 1024 * 1024,
+        stderr: 1024 * 1024,
+    };
+}
+
+/// 标记 exec 派生出的全部进程的环境变量
+const EXEC_TAG_VAR: &str = "RUN_CODE_EXEC_ID";
+
+/// 杀掉进程的超时时间
+const KILL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 在容器中执行 `bash -c <cmd>`，stdin 不为空时通过 attach 流写入后关闭
+///
+/// 命令通过 setsid 在新的进程组中运行；宿主机超时或输出超过 limits 后杀掉它派生的全部进程（见 [`kill_exec`]），
+/// 不让死循环、疯狂打印等进程残留在长期运行的容器中。
+pub async fn exec_in_container(
+    container: &str,
+    cmd: &str,
+    stdin: Option<&[u8]>,
+    run_timeout: Duration,
+    limits: OutputLimits,
+) -> Result<ExecOutput> {
+    let docker = get_docker();
+    let tag = Uuid::new_v4().simple().to_string();
+    let env = format!("{}={}", EXEC_TAG_VAR, tag);
+
+    let exec = docker
+        .create_exec(
+            container,
+            CreateExecOptions {
+                cmd: Some(vec!["setsid", "-w", "bash", "-c", cmd]),
+                env: Some(vec![env.as_str()]),
+                attach_stdin: Some(stdin.is_some()),
+                attach_stdout: Some(true),
+                attach_stderr: Some(true),
+                tty: Some(false),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    let start = Instant::now();
+    let mut stdout = BytesMut::with_capacity(8192.min(limits.stdout));
+    let mut stderr = BytesMut::new();
+    let mut stdout_truncated = false;
+    let mut stderr_truncated = false;
+
+    let exec_future = async {
+        if let StartExecResults::Attached {
+            mut output,
+            mut input,
+        } = docker.start_exec(&exec.id, None).await?
+        {
+            if let Some(data) = stdin {
+                input.write_all(data).await?;
+                // 关闭写端，程序读到 EOF
+                input.shutdown().await?;
+            }
+
+            while let Some(result) = output.next().await {
+                match result {
+                    Ok(LogOutput::StdOut { message }) => {
+                        stdout_truncated = !append_capped(&mut stdout, &message, limits.stdout);
+                    }
+                    Ok(LogOutput::StdErr { message }) => {
+                        stderr_truncated = !append_capped(&mut stderr, &message, limits.stderr);
+                    }
+                    _ => {}
+                }
+                // 超过上限后不再读取，剩余输出随进程一起丢弃
+                if stdout_truncated || stderr_truncated {
+                    break;
+                }
+            }
+        }
+        Ok::<(), anyhow::Error>(())
+    };
+
+    // Rust层超时保护
+    let timed_out = match timeout(run_timeout, exec_future).await {
+        Ok(inner) => {
+            inner?;
+            false
+        }
+        Err(_) => true,
+    };
+    let elapsed = start.elapsed();
+
+    let exit_code = if timed_out || stdout_truncated || stderr_truncated {
+        kill_exec(container, &exec.id, &tag).await;
+        None
+    } else {
+        docker.inspect_exec(&exec.id).await?.exit_code
+    };
+
+    Ok(ExecOutput {
+        stdout: String::from_utf8_lossy(&stdout).to_string(),
+        stderr: String::from_utf8_lossy(&stderr).to_string(),
+        exit_code,
+        timed_out,
+        stdout_truncated,
+        stderr_truncated,
+        elapsed,
+    })
+}
+
+/// 在不超过 limit 的前提下追加，放不下的部分丢弃并返回 false
+fn append_capped(buffer: &mut BytesMut, message: &[u8], limit: usize) -> bool {
+    let room = limit.saturating_sub(buffer.len());
+    buffer.extend_from_slice(&message[..message.len().min(room)]);
+    message.len() <= room
+}
+
+/// 宿主机超时或输出超限后杀掉 exec 仍在运行的进程
+///
+/// inspect_exec 给出的 PID 属于宿主机的命名空间，无法在容器内直接使用，
+/// 因此先按环境变量标记找到该 exec 派生出的进程，再按父进程号找出它们的全部后代：
+/// 清空环境变量后 setsid 的进程不带标记，但仍是启动器（child subreaper）的后代。
+/// 找到的进程先 SIGSTOP，直到一轮扫描没有新增（停住的进程无法再 fork），再一起 SIGKILL。
+///
+/// 不在启动器下运行的命令（如编译）派生的进程一旦成为孤儿会挂到容器的 1 号进程下，无法再找到；
+/// 这类命令只运行受信任的编译器。
+async fn kill_exec(container: &str, exec_id: &str, tag: &str) {
+    let docker = get_docker();
+    match docker.inspect_exec(exec_id).await {
+        Ok(inspect) if inspect.running == Some(false) => return,
+        Ok(inspect) => println!("🔪 Killing exec {} (host pid {:?})", exec_id, inspect.pid),
+        Err(e) => eprintln!("⚠️  Failed to inspect exec {}: {}", exec_id, e),
+    }
+
+    let kill_cmd = kill_script(tag);
+    let killed = async {
+        let exec = docker
+            .create_exec(
+                container,
+                CreateExecOptions {
+                    cmd: Some(vec!["bash", "-c", kill_cmd.as_str()]),
+                    attach_stdout: Some(true),
+                    attach_stderr: Some(true),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        if let StartExecResults::Attached { mut output, .. } =
+            docker.start_exec(&exec.id, None).await?
+        {
+            while output.next().await.is_some() {}
+        }
+        Ok::<(), anyhow::Error>(())
+    };
+
+    match timeout(KILL_TIMEOUT, killed).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => eprintln!("⚠️  Failed to kill exec {}: {}", exec_id, e),
+        Err(_) => eprintln!("⚠️  Timed out killing exec {}", exec_id),
+    }
+}
+
+/// 杀掉带有标记的进程及其全部后代的脚本，见 [`kill_exec`]
+fn kill_script(tag: &str) -> String {
+    format!(
+        "pids=' '; \
+         for p in /proc/[0-9]*; do \
+             if {{ tr '\\0' '\\n' < $p/environ; }} 2>/dev/null | grep -qx '{}={}'; then \
+                 pids=\"$pids${{p#/proc/}} \"; \
+             fi; \
+         done; \
+         grown=1; \
+         while [ $grown = 1 ]; do \
+             grown=0; \
+             for pid in $pids; do kill -STOP $pid 2>/dev/null; done; \
+             for p in /proc/[0-9]*; do \
+                 pid=${{p#/proc/}}; \
+                 case \"$pids\" in *\" $pid \"*) continue ;; esac; \
+                 ppid=$(sed -n 's/^PPid:[[:space:]]*//p' $p/status 2>/dev/null); \
+                 case \"$pids\" in *\" $ppid \"*) pids=\"$pids$pid \"; grown=1 ;; esac; \
+             done; \
+         done; \
+         for pid in $pids; do kill -KILL $pid 2>/dev/null; done; true",
+        EXEC_TAG_VAR, tag
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn kill_script_reaches_detached_descendants() {
+        let tag = Uuid::new_v4().simple().to_string();
+        let pid_file = std::env::temp_dir().join(format!("kill_script_{}", tag));
+        // 子进程 setsid 并清空环境变量，不再带有标记，也不在原来的进程组中
+        let mut parent = Command::new("bash")
+            .args([
+                "-c",
+                &format!(
+                    "setsid env -i sleep 300 & echo $! > {}; wait",
+                    pid_file.display()
+                ),
+            ])
+            .env(EXEC_TAG_VAR, &tag)
+            .spawn()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        let child = std::fs::read_to_string(&pid_file).unwrap();
+        std::fs::remove_file(&pid_file).unwrap();
+
+        let status = Command::new("bash")
+            .args(["-c", &kill_script(&tag)])
+            .env_remove(EXEC_TAG_VAR)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        // 两个进程都被 SIGKILL 终止，sleep 已退出或只剩等待回收的僵尸进程
+        assert_eq!(parent.wait().unwrap().code(), None);
+        let exited = (0..50).any(|_| {
+            let stat =
+                std::fs::read_to_string(format!("/proc/{}/stat", child.trim())).unwrap_or_default();
+            let state = stat.rsplit(')').next().unwrap_or("").trim_start();
+            std::thread::sleep(Duration::from_millis(20));
+            stat.is_empty() || state.starts_with('Z')
+        });
+        assert!(exited);
+    }
+}
@@ -1 +1,6 @@
+pub mod archive;
 pub mod client;
+pub mod exec;
+pub mod pool;
+pub mod reaper;
+pub mod sandbox;
@@ -0,0 +1,206 @@
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+use crate::config::{Config, SandboxConfig};
+use crate::docker::sandbox::{Target, create_ephemeral, remove_container};
+use crate::registry::language_registry::LanguageRegistry;
+
+/// 没有取用时检查过期、补充容器的间隔
+const REFILL_INTERVAL: Duration = Duration::from_secs(5);
+
+static POOL: OnceCell<Arc<ContainerPool>> = OnceCell::new();
+
+/// 临时容器的预热池：按镜像预先创建并启动若干空闲容器，
+/// 每次提交取走一个，用完即删除，由后台任务补足
+struct ContainerPool {
+    limits: SandboxConfig,
+    max_lifetime: Duration,
+    images: Mutex<HashMap<String, ImagePool>>,
+    refill_needed: Notify,
+}
+
+struct ImagePool {
+    /// 需要保持的空闲容器数
+    target: usize,
+    idle: VecDeque<IdleContainer>,
+    stats: PoolStats,
+}
+
+struct IdleContainer {
+    name: String,
+    created: Instant,
+}
+
+/// 单个镜像的池状态
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PoolStats {
+    pub target: usize,
+    pub idle: usize,
+    /// 从池中取到容器的次数
+    pub hits: u64,
+    /// 池为空、临时创建容器的次数
+    pub misses: u64,
+    /// 池创建的容器总数
+    pub created: u64,
+    /// 空闲超过 max_lifetime 被删除的容器数
+    pub expired: u64,
+}
+
+/// 为使用临时容器的语言启动预热池，池大小为 0 的语言不预热
+pub fn init_pool(config: &Config, registry: &LanguageRegistry) {
+    let mut images: HashMap<String, ImagePool> = HashMap::new();
+    for (id, entry) in registry.iter() {
+        let Target::Ephemeral { image } = &entry.target else {
+            continue;
+        };
+        let target = config
+            .pool
+            .min_idle
+            .get(id)
+            .copied()
+            .unwrap_or(0)
+            .max(config.pool.size);
+        if target == 0 {
+            continue;
+        }
+        // 多个语言共用一个镜像时取较大的数量
+        let pool = images.entry(image.clone()).or_insert_with(|| ImagePool {
+            target: 0,
+            idle: VecDeque::new(),
+            stats: PoolStats::default(),
+        });
+        pool.target = pool.target.max(target);
+    }
+    if images.is_empty() {
+        return;
+    }
+
+    for (image, pool) in &images {
+        println!("♨️  Warming {} container(s) for {}", pool.target, image);
+    }
+
+    let pool = Arc::new(ContainerPool {
+        limits: config.sandbox.clone(),
+        max_lifetime: Duration::from_secs(config.pool.max_lifetime),
+        images: Mutex::new(images),
+        refill_needed: Notify::new(),
+    });
+    if POOL.set(pool.clone()).is_err() {
+        return;
+    }
+
+    tokio::spawn(async move {
+        loop {
+            pool.refill().await;
+            tokio::select! {
+                _ = pool.refill_needed.notified() => {}
+                _ = tokio::time::sleep(REFILL_INTERVAL) => {}
+            }
+        }
+    });
+}
+
+/// 从池中取出一个该镜像的空闲容器，池未启用或为空时返回 None
+pub fn take(image: &str) -> Option<String> {
+    let pool = POOL.get()?;
+    let taken = {
+        let mut images = pool.images.lock().unwrap();
+        let image_pool = images.get_mut(image)?;
+        // 过期的容器留给后台任务删除
+        let fresh = image_pool
+            .idle
+            .iter()
+            .position(|idle| idle.created.elapsed() < pool.max_lifetime);
+        let taken = fresh.and_then(|index| image_pool.idle.remove(index));
+        match taken {
+            Some(_) => image_pool.stats.hits += 1,
+            None => image_pool.stats.misses += 1,
+        }
+        taken
+    };
+    pool.refill_needed.notify_one();
+    taken.map(|idle| idle.name)
+}
+
+/// 池中仍空闲的容器，后台清理任务不应删除它们
+pub fn idle_containers() -> HashSet<String> {
+    let Some(pool) = POOL.get() else {
+        return HashSet::new();
+    };
+    let images = pool.images.lock().unwrap();
+    images
+        .values()
+        .flat_map(|image_pool| image_pool.idle.iter().map(|idle| idle.name.clone()))
+        .collect()
+}
+
+/// 各镜像的池状态，池未启用时为空
+pub fn stats() -> BTreeMap<String, PoolStats> {
+    let Some(pool) = POOL.get() else {
+        return BTreeMap::new();
+    };
+    let images = pool.images.lock().unwrap();
+    images
+        .iter()
+        .map(|(image, image_pool)| {
+            let mut stats = image_pool.stats.clone();
+            stats.target = image_pool.target;
+            stats.idle = image_pool.idle.len();
+            (image.clone(), stats)
+        })
+        .collect()
+}
+
+impl ContainerPool {
+    /// 删除过期的空闲容器，并把每个镜像的空闲容器补足到 target
+    async fn refill(&self) {
+        let (expired, deficits) = {
+            let mut images = self.images.lock().unwrap();
+            let mut expired = Vec::new();
+            let mut deficits = Vec::new();
+            for (image, image_pool) in images.iter_mut() {
+                let (stale, fresh): (VecDeque<_>, VecDeque<_>) = image_pool
+                    .idle
+                    .drain(..)
+                    .partition(|idle| idle.created.elapsed() >= self.max_lifetime);
+                image_pool.idle = fresh;
+                image_pool.stats.expired += stale.len() as u64;
+                expired.extend(stale.into_iter().map(|idle| idle.name));
+
+                let missing = image_pool.target.saturating_sub(image_pool.idle.len());
+                if missing > 0 {
+                    deficits.push((image.clone(), missing));
+                }
+            }
+            (expired, deficits)
+        };
+
+        for name in expired {
+            remove_container(&name).await;
+        }
+
+        for (image, missing) in deficits {
+            for _ in 0..missing {
+                let name = match create_ephemeral(&image, &self.limits).await {
+                    Ok(name) => name,
+                    Err(e) => {
+                        eprintln!("⚠️  Failed to warm container for {}: {}", image, e);
+                        break;
+                    }
+                };
+                let mut images = self.images.lock().unwrap();
+                if let Some(image_pool) = images.get_mut(&image) {
+                    image_pool.stats.created += 1;
+                    image_pool.idle.push_back(IdleContainer {
+                        name,
+                        created: Instant::now(),
+                    });
+                }
+            }
+        }
+    }
+}
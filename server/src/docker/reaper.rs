@@ -0,0 +1,103 @@
+use bollard::container::{ListContainersOptions, RemoveContainerOptions};
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::{Backend, Config};
+use crate::docker::client::get_docker;
+use crate::docker::exec::{OutputLimits, exec_in_container};
+use crate::docker::pool;
+use crate::docker::sandbox::SANDBOX_LABEL;
+
+/// 一次清理的超时时间
+const REAP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 启动后台任务，定期删除各共享容器中遗留的运行目录以及遗留的临时容器
+///
+/// 正常情况下运行目录与临时容器在评测结束时删除；服务重启或清理失败时留下的，
+/// 超过 orphan_max_age 秒后由这里删除。
+pub fn spawn_reaper(config: &Config) {
+    let containers: BTreeSet<String> = config
+        .languages
+        .iter()
+        .filter(|(_, lang)| lang.enabled && lang.backend == Backend::Shared)
+        .filter_map(|(id, _)| config.get_container(id))
+        .map(str::to_string)
+        .collect();
+    let max_age = config.server.orphan_max_age;
+    let interval = Duration::from_secs(config.server.reaper_interval);
+    // find 的 -mmin 以分钟为单位，向上取整
+    let max_age_mins = config.server.orphan_max_age.div_ceil(60).max(1);
+    let cmd = format!(
+        "find /tmp -maxdepth 1 -name 'main_*' -mmin +{} -print -exec rm -rf {{}} +",
+        max_age_mins
+    );
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            for container in &containers {
+                match exec_in_container(container, &cmd, None, REAP_TIMEOUT, OutputLimits::INTERNAL)
+                    .await
+                {
+                    Ok(output) => {
+                        let removed = output.stdout.lines().count();
+                        if removed > 0 {
+                            println!(
+                                "🧹 Removed {} orphaned run directories in {}",
+                                removed, container
+                            );
+                        }
+                    }
+                    Err(e) => eprintln!("⚠️  Reaper failed in {}: {}", container, e),
+                }
+            }
+            if let Err(e) = remove_stale_sandboxes(max_age).await {
+                eprintln!("⚠️  Reaper failed to remove sandboxes: {}", e);
+            }
+        }
+    });
+}
+
+/// 删除创建时间超过 max_age 秒的临时容器，预热池中空闲的容器除外
+async fn remove_stale_sandboxes(max_age: u64) -> anyhow::Result<()> {
+    let docker = get_docker();
+    let pooled = pool::idle_containers();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+
+    let sandboxes = docker
+        .list_containers(Some(ListContainersOptions {
+            all: true,
+            filters: HashMap::from([("label", vec![SANDBOX_LABEL])]),
+            ..Default::default()
+        }))
+        .await?;
+
+    for sandbox in sandboxes {
+        let (Some(id), Some(created)) = (sandbox.id, sandbox.created) else {
+            continue;
+        };
+        if now - created < max_age as i64 {
+            continue;
+        }
+        let names = sandbox.names.unwrap_or_default();
+        if names
+            .iter()
+            .any(|name| pooled.contains(name.trim_start_matches('/')))
+        {
+            continue;
+        }
+        docker
+            .remove_container(
+                &id,
+                Some(RemoveContainerOptions {
+                    force: true,
+                    ..Default::default()
+                }),
+            )
+            .await?;
+        println!("🧹 Removed stale sandbox container {}", id);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use anyhow::Result;
+use bollard::container::{
+    Config as ContainerConfig, CreateContainerOptions, RemoveContainerOptions,
+    StartContainerOptions,
+};
+use bollard::models::HostConfig;
+use std::collections::HashMap;
+use std::fmt;
+use uuid::Uuid;
+
+use crate::config::SandboxConfig;
+use crate::docker::archive::{extract_files, upload_files};
+use crate::docker::client::get_docker;
+use crate::docker::pool;
+
+/// 临时容器带有的标签，用于找出服务异常退出后遗留的容器
+pub const SANDBOX_LABEL: &str = "run_code.sandbox";
+
+/// 语言的代码运行在哪里
+#[derive(Debug, Clone)]
+pub enum Target {
+    /// 所有提交共用的长期运行容器
+    Shared { container: String },
+    /// 每次提交从镜像创建的临时容器
+    Ephemeral { image: String },
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Shared { container } => write!(f, "container: {}", container),
+            Target::Ephemeral { image } => write!(f, "ephemeral, image: {}", image),
+        }
+    }
+}
+
+/// 一次提交使用的容器，用完后调用 release
+pub struct Sandbox {
+    pub container: String,
+    ephemeral: bool,
+}
+
+impl Sandbox {
+    /// 共享容器直接使用；临时容器按资源限制创建并启动
+    pub async fn acquire(target: &Target, limits: &SandboxConfig) -> Result<Self> {
+        match target {
+            Target::Shared { container } => Ok(Self {
+                container: container.clone(),
+                ephemeral: false,
+            }),
+            Target::Ephemeral { image } => {
+                // 优先使用预热池中的容器，池为空时现场创建
+                let container = match pool::take(image) {
+                    Some(container) => container,
+                    None => create_ephemeral(image, limits).await?,
+                };
+                Ok(Self {
+                    container,
+                    ephemeral: true,
+                })
+            }
+        }
+    }
+
+    pub fn is_ephemeral(&self) -> bool {
+        self.ephemeral
+    }
+
+    /// 容器所用镜像的 id
+    pub async fn image_id(&self) -> Result<String> {
+        get_docker()
+            .inspect_container(&self.container, None)
+            .await?
+            .image
+            .ok_or_else(|| anyhow::anyhow!("无法获取容器 {} 的镜像", self.container))
+    }
+
+    /// 上传文件到容器中的 dir 目录
+    ///
+    /// 临时容器的根文件系统只读，/tmp 为 tmpfs，归档接口无法写入，
+    /// 改为在容器内用 tar 从标准输入解包。
+    pub async fn upload(&self, dir: &str, files: &[(&str, &[u8])]) -> Result<()> {
+        if self.ephemeral {
+            extract_files(&self.container, dir, files).await
+        } else {
+            upload_files(&self.container, dir, files).await
+        }
+    }
+
+    /// 删除临时容器，删除失败时由后台清理任务兜底
+    pub async fn release(self) {
+        if self.ephemeral {
+            remove_container(&self.container).await;
+        }
+    }
+}
+
+/// 强制删除容器，失败时只记录日志
+pub async fn remove_container(container: &str) {
+    let removed = get_docker()
+        .remove_container(
+            container,
+            Some(RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            }),
+        )
+        .await;
+    if let Err(e) = removed {
+        eprintln!("⚠️  Failed to remove container {}: {}", container, e);
+    }
+}
+
+/// 按资源限制从镜像创建并启动一个临时容器，返回容器名
+pub async fn create_ephemeral(image: &str, limits: &SandboxConfig) -> Result<String> {
+    let docker = get_docker();
+    let name = format!("run_code_{}", Uuid::new_v4().simple());
+    let mb = 1024 * 1024;
+
+    let host_config = HostConfig {
+        memory: Some(limits.memory_mb * mb),
+        memory_swap: Some(limits.memory_swap_mb * mb),
+        nano_cpus: Some((limits.cpus * 1e9) as i64),
+        pids_limit: Some(limits.pids_limit),
+        network_mode: Some("none".to_string()),
+        readonly_rootfs: Some(true),
+        tmpfs: Some(HashMap::from([(
+            "/tmp".to_string(),
+            format!("rw,exec,nosuid,size={}m", limits.tmpfs_mb),
+        )])),
+        cap_drop: Some(vec!["ALL".to_string()]),
+        security_opt: Some(vec!["no-new-privileges".to_string()]),
+        ..Default::default()
+    };
+
+    docker
+        .create_container(
+            Some(CreateContainerOptions {
+                name: name.as_str(),
+                platform: None,
+            }),
+            ContainerConfig {
+                image: Some(image),
+                // 容器只负责保持运行，代码通过 exec 执行
+                cmd: Some(vec!["sleep", "infinity"]),
+                // 编译器的缓存目录（如 Go 的 GOCACHE）默认在 HOME 下，只读根文件系统中需改到 tmpfs
+                env: Some(vec!["HOME=/tmp"]),
+                labels: Some(HashMap::from([(SANDBOX_LABEL, "true")])),
+                network_disabled: Some(true),
+                host_config: Some(host_config),
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("创建临时容器失败 ({}): {}", image, e))?;
+
+    if let Err(e) = docker
+        .start_container(&name, None::<StartContainerOptions<String>>)
+        .await
+    {
+        remove_container(&name).await;
+        anyhow::bail!("启动临时容器失败 ({}): {}", image, e);
+    }
+
+    Ok(name)
+}
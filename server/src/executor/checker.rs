@@ -0,0 +1,53 @@
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use crate::config::Config;
+use crate::executor::universal_executor::run_program;
+use crate::model::request::Checker;
+use crate::parser::parse_output::{apply_checker_verdicts, parse_checker_output};
+use crate::registry::language_registry::LanguageEntry;
+
+/// 运行特判程序，用它的判定覆盖 output 中每个用例的 passed
+///
+/// 每个用例向 checker 的标准输入写一行 JSON：
+/// `{"index": 0, "input": [...], "expected": ..., "output": ...}`，
+/// checker 按顺序每个用例向标准输出写一行 `PASS <说明>` 或 `FAIL <说明>`，标准错误不参与判定。
+pub async fn run_checker(
+    language: &LanguageEntry,
+    checker: &Checker,
+    output: &mut Value,
+    config: &Config,
+) -> Result<()> {
+    // 解答本身没有产生有效输出时无需特判
+    let Some(cases) = output.get("cases").and_then(Value::as_array) else {
+        return Ok(());
+    };
+
+    let mut stdin = String::new();
+    for (index, case) in cases.iter().enumerate() {
+        let line = json!({
+            "index": index,
+            "input": case["input"],
+            "expected": case["expected"],
+            "output": case["output"],
+        });
+        stdin.push_str(&line.to_string());
+        stdin.push('\n');
+    }
+
+    // 只解析 stdout，checker 写到 stderr 的调试信息不会被当成判定
+    let verdicts = match run_program(language, &checker.code, Some(&stdin), config).await {
+        Ok(output) if output.timed_out => {
+            eprintln!("⏰ Checker timed out (killed by host)");
+            Vec::new()
+        }
+        Ok(output) => parse_checker_output(&output.stdout),
+        Err(e) => {
+            eprintln!("❌ Checker internal error: {}", e);
+            Vec::new()
+        }
+    };
+    apply_checker_verdicts(output, &verdicts, &checker.language);
+
+    Ok(())
+}
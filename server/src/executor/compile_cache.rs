@@ -0,0 +1,198 @@
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::config::{CompileCacheConfig, LanguageConfig};
+use crate::docker::sandbox::Sandbox;
+
+/// 共享容器内的编译缓存，每个条目是以缓存键命名的目录，
+/// 保存可执行文件 `bin` 与编译器输出 `out`
+///
+/// 命中时更新目录的修改时间，写入新条目后按修改时间淘汰最久未用的条目。
+/// 只缓存共享容器中的编译结果：临时容器用完即删，缓存没有意义。
+///
+/// 缓存目录属于 root，共享容器中的用户代码以 RUN_UID 运行，无法写入。
+/// 此外服务端在编译成功后记下可执行文件的 SHA-256，命中时复制出的文件必须与之一致，
+/// 摘要不符或服务端重启后没有记录时都按未命中处理，重新编译并覆盖条目。
+pub struct CompileCache<'a> {
+    dir: &'a str,
+    max_entries: usize,
+    key: String,
+    /// 摘要表中的键：容器名与缓存键
+    digest_key: String,
+}
+
+/// 服务端记下的缓存条目摘要，值为可执行文件的 SHA-256 与最近使用时间
+static DIGESTS: Lazy<Mutex<HashMap<String, (String, Instant)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+impl<'a> CompileCache<'a> {
+    /// 语言或容器不支持缓存时返回 None
+    pub async fn for_source(
+        sandbox: &Sandbox,
+        lang_config: &LanguageConfig,
+        config: &'a CompileCacheConfig,
+        source: &str,
+    ) -> Result<Option<Self>> {
+        let Some(compile_cmd) = &lang_config.compile_cmd else {
+            return Ok(None);
+        };
+        if !config.enabled || !lang_config.compile_cache || sandbox.is_ephemeral() {
+            return Ok(None);
+        }
+
+        let image_id = sandbox.image_id().await?;
+        let key = cache_key(source, compile_cmd, &image_id);
+        Ok(Some(Self {
+            dir: &config.dir,
+            max_entries: config.max_entries,
+            digest_key: format!("{}/{}", sandbox.container, key),
+            key,
+        }))
+    }
+
+    /// 命中时把可执行文件复制到 binary，先输出一行复制出的文件的 SHA-256，再输出缓存的编译器输出；
+    /// 未命中时以非 0 退出
+    pub fn lookup_cmd(&self, binary: &str) -> String {
+        let entry = self.entry();
+        format!(
+            "test -d {0} && cp {0}/bin {1} && touch {0} && {2} && cat {0}/out",
+            entry,
+            binary,
+            self.digest_cmd(binary)
+        )
+    }
+
+    /// 校验 lookup_cmd 的输出，摘要与记录一致时返回缓存的编译器输出
+    pub fn verify<'s>(&self, stdout: &'s str) -> Option<&'s str> {
+        let (digest, out) = stdout.split_once('\n')?;
+        let mut digests = DIGESTS.lock().unwrap();
+        let (recorded, used) = digests.get_mut(&self.digest_key)?;
+        if recorded != digest {
+            return None;
+        }
+        *used = Instant::now();
+        Some(out)
+    }
+
+    /// 输出 binary 的 SHA-256，编译成功后运行，结果交给 record
+    pub fn digest_cmd(&self, binary: &str) -> String {
+        format!("sha256sum {} | cut -d' ' -f1", binary)
+    }
+
+    /// 记下刚写入的条目的摘要，超过上限时丢弃最久未用的记录
+    pub fn record(&self, stdout: &str) {
+        let mut digests = DIGESTS.lock().unwrap();
+        digests.insert(
+            self.digest_key.clone(),
+            (stdout.trim().to_string(), Instant::now()),
+        );
+        // 多个共享容器共用一张表，按每个容器的条目上限留出余量；
+        // 容器内的条目被淘汰后，多留的记录只会让查找落空
+        while digests.len() > self.max_entries * 4 {
+            let Some(oldest) = digests
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            digests.remove(&oldest);
+        }
+    }
+
+    /// 编译并在成功后写入缓存，退出码与输出与直接编译相同
+    ///
+    /// 条目先写入临时目录再改名，并发写入同一个键时只有一个生效；
+    /// 已有的条目（如摘要校验失败的）先删除再替换。
+    pub fn compile_cmd(&self, compile_cmd: &str, binary: &str) -> String {
+        let entry = self.entry();
+        format!(
+            "OUT=$({compile}); RC=$?; printf '%s' \"$OUT\"; \
+             if [ $RC -eq 0 ]; then {{ \
+                 TMP={entry}.tmp.$$; \
+                 mkdir -p $TMP && cp {binary} $TMP/bin && printf '%s' \"$OUT\" > $TMP/out \
+                     && {{ rm -rf {entry}; mv -T $TMP {entry} || rm -rf $TMP; }}; \
+                 ls -t {dir} | grep -v '\\.tmp\\.' | tail -n +{keep} | xargs -r -I{{}} rm -rf {dir}/{{}}; \
+             }} >/dev/null 2>&1; fi; \
+             exit $RC",
+            compile = compile_cmd,
+            entry = entry,
+            binary = binary,
+            dir = self.dir,
+            keep = self.max_entries + 1
+        )
+    }
+
+    fn entry(&self) -> String {
+        format!("{}/{}", self.dir, self.key)
+    }
+}
+
+/// 缓存键：测试代码、编译命令模板与镜像 id 的 SHA-256
+///
+/// 编译命令使用模板而不是替换后的命令，替换后的路径每次运行都不同。
+pub fn cache_key(source: &str, compile_cmd: &str, image_id: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [source, compile_cmd, image_id] {
+        // 带上长度，避免不同的拆分拼接出相同的输入
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_depends_on_every_part() {
+        let key = cache_key("int main() {}", "gcc {source} -o {binary}", "sha256:1");
+        assert_eq!(key.len(), 64);
+        assert_eq!(
+            key,
+            cache_key("int main() {}", "gcc {source} -o {binary}", "sha256:1")
+        );
+        assert_ne!(
+            key,
+            cache_key("int main() {}", "gcc {source} -o {binary}", "sha256:2")
+        );
+        assert_ne!(cache_key("ab", "c", "d"), cache_key("a", "bc", "d"));
+    }
+
+    #[test]
+    fn hit_requires_recorded_digest() {
+        let cache = CompileCache {
+            dir: "/var/cache/run_code",
+            max_entries: 1,
+            key: "k".to_string(),
+            digest_key: "test-container/k".to_string(),
+        };
+        let hit = "abc\nmain.c:1: warning\n";
+        // 服务端没有记录（如重启后）时不使用缓存
+        assert_eq!(cache.verify(hit), None);
+
+        cache.record("abc\n");
+        assert_eq!(cache.verify(hit), Some("main.c:1: warning\n"));
+        assert_eq!(cache.verify("abd\nmain.c:1: warning\n"), None);
+        assert_eq!(cache.verify("abc"), None);
+
+        // 超过上限时丢弃最久未用的记录
+        let others: Vec<_> = (0..4)
+            .map(|index| CompileCache {
+                key: index.to_string(),
+                digest_key: format!("test-container/{}", index),
+                ..cache
+            })
+            .collect();
+        for other in &others {
+            other.record("def");
+        }
+        assert_eq!(cache.verify(hit), None);
+        assert_eq!(others[3].verify("def\n"), Some(""));
+    }
+}
@@ -0,0 +1,296 @@
+use anyhow::Result;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+
+use crate::model::request::{ComplexityOptions, TestCase};
+
+/// 每个用例最多重复运行的次数
+pub const MAX_REPEATS: usize = 10;
+
+/// 拟合至少需要的不同规模数
+const MIN_SIZES: usize = 3;
+
+/// 候选的增长模型，按从简单到复杂排列，拟合得一样好时取靠前的
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    Constant,
+    Logarithmic,
+    Linear,
+    Linearithmic,
+    Quadratic,
+    Cubic,
+    Exponential,
+}
+
+impl Model {
+    const ALL: [Model; 7] = [
+        Model::Constant,
+        Model::Logarithmic,
+        Model::Linear,
+        Model::Linearithmic,
+        Model::Quadratic,
+        Model::Cubic,
+        Model::Exponential,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Model::Constant => "O(1)",
+            Model::Logarithmic => "O(log n)",
+            Model::Linear => "O(n)",
+            Model::Linearithmic => "O(n log n)",
+            Model::Quadratic => "O(n²)",
+            Model::Cubic => "O(n³)",
+            Model::Exponential => "O(2ⁿ)",
+        }
+    }
+
+    fn eval(self, n: f64) -> f64 {
+        match self {
+            Model::Constant => 1.0,
+            Model::Logarithmic => n.log2(),
+            Model::Linear => n,
+            Model::Linearithmic => n * n.log2(),
+            Model::Quadratic => n * n,
+            Model::Cubic => n * n * n,
+            Model::Exponential => n.exp2(),
+        }
+    }
+
+    /// 参数个数：常数模型只有截距
+    fn params(self) -> f64 {
+        if self == Model::Constant { 1.0 } else { 2.0 }
+    }
+}
+
+/// 最优模型的拟合结果，y ≈ intercept + coefficient · f(n)
+#[derive(Debug, Clone, Copy)]
+pub struct Fit {
+    pub model: Model,
+    /// 最优模型的 Akaike 权重，0 到 1；几个模型拟合得差不多时较低
+    pub confidence: f64,
+    pub r_squared: f64,
+    pub intercept: f64,
+    pub coefficient: f64,
+}
+
+/// 对 (n, y) 逐个候选模型做最小二乘拟合 y = a + b·f(n)，返回 AIC 最小的模型
+///
+/// 截距吸收了运行时启动等与规模无关的开销。b 为负或 f(n) 溢出的模型不参与比较。
+/// 不同的 n 少于 3 个时无法区分模型，返回 None。
+pub fn fit(points: &[(f64, f64)]) -> Option<Fit> {
+    let mut sizes: Vec<f64> = points.iter().map(|&(n, _)| n).collect();
+    sizes.sort_by(f64::total_cmp);
+    sizes.dedup();
+    if sizes.len() < MIN_SIZES {
+        return None;
+    }
+
+    let m = points.len() as f64;
+    let mean = points.iter().map(|&(_, y)| y).sum::<f64>() / m;
+    let sst: f64 = points.iter().map(|&(_, y)| (y - mean).powi(2)).sum();
+    // 残差为 0 时 AIC 趋于负无穷，设下限让完全拟合的模型之间按参数个数比较
+    let floor = (sst * 1e-12).max(f64::MIN_POSITIVE);
+
+    let mut fits = Vec::new();
+    for model in Model::ALL {
+        let Some((intercept, coefficient, ssr)) = least_squares(points, model) else {
+            continue;
+        };
+        let aic = m * (ssr.max(floor) / m).ln() + 2.0 * model.params();
+        let r_squared = if sst > 0.0 { 1.0 - ssr / sst } else { 1.0 };
+        fits.push((
+            Fit {
+                model,
+                confidence: 0.0,
+                r_squared,
+                intercept,
+                coefficient,
+            },
+            aic,
+        ));
+    }
+
+    let best_aic = fits
+        .iter()
+        .map(|&(_, aic)| aic)
+        .fold(f64::INFINITY, f64::min);
+    let weights: f64 = fits
+        .iter()
+        .map(|&(_, aic)| (-(aic - best_aic) / 2.0).exp())
+        .sum();
+    let (mut best, _) = fits.into_iter().find(|&(_, aic)| aic == best_aic)?;
+    best.confidence = 1.0 / weights;
+    Some(best)
+}
+
+/// 单个模型的截距、系数与残差平方和
+fn least_squares(points: &[(f64, f64)], model: Model) -> Option<(f64, f64, f64)> {
+    let m = points.len() as f64;
+    let mean_y = points.iter().map(|&(_, y)| y).sum::<f64>() / m;
+    if model == Model::Constant {
+        let ssr = points.iter().map(|&(_, y)| (y - mean_y).powi(2)).sum();
+        return Some((mean_y, 0.0, ssr));
+    }
+
+    let xs: Vec<f64> = points.iter().map(|&(n, _)| model.eval(n)).collect();
+    let mean_x = xs.iter().sum::<f64>() / m;
+    let sxx: f64 = xs.iter().map(|x| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = xs
+        .iter()
+        .zip(points)
+        .map(|(x, &(_, y))| (x - mean_x) * (y - mean_y))
+        .sum();
+    if !sxx.is_finite() || sxx <= 0.0 {
+        return None;
+    }
+
+    let coefficient = sxy / sxx;
+    if !coefficient.is_finite() || coefficient < 0.0 {
+        return None;
+    }
+    let intercept = mean_y - coefficient * mean_x;
+    let ssr = xs
+        .iter()
+        .zip(points)
+        .map(|(x, &(_, y))| (y - intercept - coefficient * x).powi(2))
+        .sum();
+    Some((intercept, coefficient, ssr))
+}
+
+/// 校验复杂度分析的选项与用例
+pub fn check_cases(cases: &[TestCase], options: &ComplexityOptions) -> Result<()> {
+    if !(1..=MAX_REPEATS).contains(&options.repeats) {
+        anyhow::bail!("complexity.repeats 必须在 1 到 {} 之间", MAX_REPEATS);
+    }
+
+    let mut sizes = Vec::with_capacity(cases.len());
+    for (index, case) in cases.iter().enumerate() {
+        match case.size {
+            Some(size) if size > 0 => sizes.push(size),
+            _ => anyhow::bail!(
+                "复杂度分析要求每个用例设置大于 0 的 size，用例 {} 没有",
+                index
+            ),
+        }
+    }
+    sizes.sort_unstable();
+    sizes.dedup();
+    if sizes.len() < MIN_SIZES {
+        anyhow::bail!(
+            "复杂度分析至少需要 {} 种不同的 size，当前只有 {} 种",
+            MIN_SIZES,
+            sizes.len()
+        );
+    }
+    Ok(())
+}
+
+/// 按规模收集的耗时与内存峰值样本
+#[derive(Default)]
+pub struct Samples {
+    by_size: BTreeMap<u64, (Vec<f64>, Vec<f64>)>,
+}
+
+impl Samples {
+    pub fn add(&mut self, size: u64, time_ms: f64, memory_kb: i64) {
+        let (times, memory) = self.by_size.entry(size).or_default();
+        times.push(time_ms);
+        memory.push(memory_kb as f64);
+    }
+
+    /// 每个规模取中位数后分别拟合耗时与内存，生成返回结果中的 complexity
+    pub fn report(&self, repeats: usize) -> Value {
+        let mut points = Vec::with_capacity(self.by_size.len());
+        let mut time = Vec::with_capacity(self.by_size.len());
+        let mut space = Vec::with_capacity(self.by_size.len());
+
+        for (&size, (times, memory)) in &self.by_size {
+            let time_ms = median(times);
+            let memory_kb = median(memory);
+            points.push(json!({
+                "size": size,
+                "time_ms": time_ms,
+                "memory_kb": memory_kb,
+                "samples": times.len()
+            }));
+            time.push((size as f64, time_ms));
+            space.push((size as f64, memory_kb));
+        }
+
+        json!({
+            "repeats": repeats,
+            "points": points,
+            "time": fit_json(&time),
+            "space": fit_json(&space)
+        })
+    }
+}
+
+fn fit_json(points: &[(f64, f64)]) -> Value {
+    match fit(points) {
+        Some(fit) => json!({
+            "model": fit.model.label(),
+            "confidence": fit.confidence,
+            "r_squared": fit.r_squared,
+            "intercept": fit.intercept,
+            "coefficient": fit.coefficient
+        }),
+        None => Value::Null,
+    }
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(model: impl Fn(f64) -> f64) -> Vec<(f64, f64)> {
+        [1000.0, 2000.0, 4000.0, 8000.0, 16000.0]
+            .iter()
+            .map(|&n| (n, model(n)))
+            .collect()
+    }
+
+    #[test]
+    fn picks_generating_model() {
+        let linear = fit(&sample(|n| 0.5 + 0.002 * n)).unwrap();
+        assert_eq!(linear.model, Model::Linear);
+        assert!(linear.confidence > 0.9);
+        assert!((linear.coefficient - 0.002).abs() < 1e-9);
+
+        let quadratic = fit(&sample(|n| 3.0 + 1e-6 * n * n)).unwrap();
+        assert_eq!(quadratic.model, Model::Quadratic);
+
+        let constant = fit(&sample(|_| 1200.0)).unwrap();
+        assert_eq!(constant.model, Model::Constant);
+        assert_eq!(constant.r_squared, 1.0);
+
+        assert!(fit(&[(10.0, 1.0), (10.0, 2.0), (20.0, 3.0)]).is_none());
+    }
+
+    #[test]
+    fn reports_medians_per_size() {
+        let mut samples = Samples::default();
+        for (size, time_ms, memory_kb) in [(10, 0.3, 100), (10, 0.1, 100), (10, 0.2, 100)] {
+            samples.add(size, time_ms, memory_kb);
+        }
+        samples.add(20, 0.4, 200);
+        samples.add(40, 0.8, 400);
+
+        let report = samples.report(3);
+        assert_eq!(report["points"][0]["time_ms"], json!(0.2));
+        assert_eq!(report["points"][0]["samples"], json!(3));
+        assert_eq!(report["space"]["model"], json!("O(n)"));
+    }
+}
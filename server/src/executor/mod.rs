@@ -1 +1,6 @@
-pub mod universal_executor;
\ No newline at end of file
+pub mod checker;
+pub mod compile_cache;
+pub mod complexity;
+pub mod stdio_executor;
+pub mod timing;
+pub mod universal_executor;
@@ -0,0 +1,187 @@
+use anyhow::Result;
+use serde_json::{Value, json};
+use std::time::Instant;
+
+use crate::config::{Config, LanguageConfig};
+use crate::docker::exec::exec_in_container;
+use crate::docker::sandbox::Sandbox;
+use crate::executor::checker::run_checker;
+use crate::executor::timing::{PhaseTiming, report_timings};
+use crate::executor::universal_executor::{
+    Compiled, HOST_GRACE, RunLimits, RunOptions, Workspace, compile,
+};
+use crate::model::comparison::Whitespace;
+use crate::model::request::{Checker, StdioCase};
+use crate::model::verdict::{RunFailure, Verdict};
+use crate::parser::parse_output::{overall_verdict, summarize};
+use crate::registry::language_registry::LanguageEntry;
+
+/// stdio 模式评测所需的全部输入
+pub struct StdioSpec<'a> {
+    /// 用户的完整程序
+    pub code: &'a str,
+    pub cases: &'a [StdioCase],
+    /// 标准输出的比较方式
+    pub whitespace: Whitespace,
+}
+
+/// ACM 模式：用户代码原样编译一次，每个用例单独运行一次，
+/// 用例输入随源码一起上传，重定向为标准输入，比较标准输出
+///
+/// 用例耗时为容器内测得的墙钟时间，不含 Docker 往返开销；内存为进程外测得的峰值。
+pub async fn execute_stdio(
+    lang: &str,
+    language: &LanguageEntry,
+    spec: &StdioSpec<'_>,
+    options: &RunOptions,
+    checker: Option<(&LanguageEntry, &Checker)>,
+    config: &Config,
+) -> Result<Value> {
+    let total_start = Instant::now();
+    let lang_config = &language.config;
+    let limits = RunLimits::for_language(lang_config, &config.server)
+        .with_memory_limit(options.memory_limit_mb);
+    let sandbox = Sandbox::acquire(&language.target, &config.sandbox).await?;
+    let workspace = Workspace::new(lang_config, &sandbox);
+
+    println!("⏱️  [0ms] Starting {} stdio execution", lang);
+
+    // 评测出错或超时都要删除运行目录
+    let judged = judge(&sandbox, &workspace, lang_config, spec, &limits, config).await;
+    workspace.cleanup(&sandbox).await;
+    sandbox.release().await;
+    let (compiled, mut output, run_timing) = judged?;
+
+    if let Some((checker_language, checker)) = checker.filter(|_| compiled.success) {
+        run_checker(checker_language, checker, &mut output, config).await?;
+    }
+
+    let elapsed = total_start.elapsed();
+    println!("⏱️  [{}ms] Completed", elapsed.as_millis());
+
+    let verdict = if compiled.success {
+        overall_verdict(&output)
+    } else {
+        Verdict::CompileError
+    };
+
+    let mut response = json!({
+        "language": lang,
+        "verdict": verdict,
+        "execution_time_ms": elapsed.as_millis(),
+        "output": output
+    });
+    report_timings(
+        &mut response,
+        compiled.timing,
+        run_timing,
+        elapsed.as_secs_f64() * 1000.0,
+    );
+    if let Some(report) = compiled.report {
+        response["compile"] = report;
+    }
+    Ok(response)
+}
+
+/// 上传源码与全部用例输入并编译，编译成功后逐个运行用例，返回各用例运行耗时之和
+async fn judge(
+    sandbox: &Sandbox,
+    workspace: &Workspace,
+    lang_config: &LanguageConfig,
+    spec: &StdioSpec<'_>,
+    limits: &RunLimits,
+    config: &Config,
+) -> Result<(Compiled, Value, PhaseTiming)> {
+    let StdioSpec {
+        code,
+        cases,
+        whitespace,
+    } = *spec;
+    let inputs: Vec<(String, &str)> = cases
+        .iter()
+        .enumerate()
+        .map(|(index, case)| (format!("case_{}.in", index), case.input.as_str()))
+        .collect();
+    let container = sandbox.container.as_str();
+    workspace.upload(sandbox, code, &inputs).await?;
+
+    let compiled = compile(
+        sandbox,
+        workspace,
+        lang_config,
+        code,
+        code,
+        &config.compile_cache,
+    )
+    .await?;
+    if !compiled.success {
+        let error = compiled.error();
+        return Ok((compiled, error, PhaseTiming::default()));
+    }
+
+    let run_cmd = workspace.run_cmd(lang_config, limits);
+    let mut results = Vec::with_capacity(cases.len());
+    let mut run_timing = PhaseTiming::default();
+
+    for (case, (input_name, _)) in cases.iter().zip(&inputs) {
+        let cmd = workspace.launched(
+            &format!("bash -c '{}' < {}", run_cmd, workspace.path(input_name)),
+            lang_config,
+            limits,
+        );
+        let run = exec_in_container(
+            container,
+            &cmd,
+            None,
+            limits.time + HOST_GRACE,
+            limits.output,
+        )
+        .await?;
+        let (stderr, timing) = workspace.split_timing(&run.stderr);
+        // 被宿主机超时中断时没有容器内计时，退回宿主机测得的时间
+        let timing = timing.unwrap_or(PhaseTiming {
+            wall_ms: run.elapsed.as_secs_f64() * 1000.0,
+            ..PhaseTiming::default()
+        });
+        run_timing += timing;
+
+        // 输出超限时进程已被终止，退出码没有意义；内存超限时进程可能因分配失败而崩溃
+        let failure = if run.stdout_truncated {
+            Some(RunFailure::output_limit("stdout", limits.output.stdout))
+        } else if run.stderr_truncated {
+            Some(RunFailure::output_limit("stderr", limits.output.stderr))
+        } else {
+            limits
+                .check(&timing)
+                .or_else(|| RunFailure::from_exit(run.exit_code, run.timed_out, stderr))
+                .map(|failure| limits.explain(failure, &timing))
+        };
+        let passed = failure.is_none() && whitespace.matches(&run.stdout, &case.expected);
+
+        let mut result = json!({
+            "input": case.input,
+            "output": run.stdout,
+            "expected": case.expected,
+            "passed": passed,
+            "verdict": Verdict::from_passed(passed),
+            "comparison": {"mode": "stdio", "whitespace": whitespace},
+            "time_ms": timing.wall_ms,
+            "cpu_time_ms": timing.cpu_ms,
+            "memory_kb": timing.peak_memory_kb
+        });
+        if let Some(failure) = failure {
+            result["verdict"] = json!(failure.verdict);
+            result["message"] = json!(failure.message);
+            if let Some(exit_code) = failure.exit_code {
+                result["exit_code"] = json!(exit_code);
+            }
+            if let Some(signal) = failure.signal {
+                result["signal"] = json!(signal);
+            }
+        }
+        results.push(result);
+    }
+
+    let output = summarize(results, run_timing.wall_ms, run_timing.peak_memory_kb);
+    Ok((compiled, output, run_timing))
+}
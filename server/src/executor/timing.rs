@@ -0,0 +1,140 @@
+use serde_json::{Value, json};
+use std::ops::AddAssign;
+use std::time::Duration;
+
+/// 容器内测得的一段耗时与内存峰值
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PhaseTiming {
+    pub wall_ms: f64,
+    /// 用户态与内核态 CPU 时间之和，包含已退出的子进程
+    pub cpu_ms: f64,
+    /// 进程树的最大常驻内存（KB），不经过启动器运行时为 0
+    pub peak_memory_kb: i64,
+}
+
+impl AddAssign for PhaseTiming {
+    fn add_assign(&mut self, other: Self) {
+        self.wall_ms += other.wall_ms;
+        self.cpu_ms += other.cpu_ms;
+        self.peak_memory_kb = self.peak_memory_kb.max(other.peak_memory_kb);
+    }
+}
+
+/// 在容器内用 bash 的 time 计时运行简单命令 cmd，并用 timeout 限制时长
+///
+/// cmd 的 stderr 不变（cmd 自带的重定向仍然生效）；结束后在 stderr 末尾追加一行 marker
+/// 和一行 `墙钟 用户态 内核态`（秒），由 split_timing 拆出。命令的退出码保留在 `$?` 中，超时时为 124。
+/// 只能计时简单命令：time 一个以 exit 结束的子 shell 时，bash 会把计时写到别处。
+pub fn timed_cmd(cmd: &str, limit: Duration, marker: &str, timing_file: &str) -> String {
+    format!(
+        "TIMEFORMAT='%3R %3U %3S'; {{ time 2>&4 timeout {} {}; }} 4>&2 2>{}; TIMED_RC=$?; \
+         printf '\\n%s\\n' {} >&2; cat {} >&2 2>/dev/null; rm -f {}; (exit $TIMED_RC)",
+        timeout_arg(limit),
+        cmd,
+        timing_file,
+        marker,
+        timing_file,
+        timing_file
+    )
+}
+
+/// 镜像中受信任的启动器，源码见 dockerfiles/launcher/launcher.c
+pub const LAUNCHER: &str = "/usr/local/bin/run_code_launcher";
+
+/// 交给启动器执行的限制
+pub struct LaunchLimits {
+    pub wall: Duration,
+    pub cpu: Option<Duration>,
+    /// 地址空间上限（字节）
+    pub memory: Option<u64>,
+    /// 运行命令的 uid，None 时沿用 exec 的用户
+    pub user: Option<u32>,
+}
+
+/// 由启动器运行简单命令 cmd，在进程外测量墙钟、CPU 时间与最大常驻内存
+///
+/// 与 timed_cmd 相同，结束后在 stderr 末尾追加一行 marker 和启动器的报告
+/// `墙钟 用户态 内核态 内存峰值(KB)`，由 split_timing 拆出。
+/// 启动器的退出码沿用 bash 的约定：墙钟超时为 124，被信号终止为 128 + 信号值。
+pub fn launched_cmd(cmd: &str, limits: &LaunchLimits, marker: &str, report_file: &str) -> String {
+    let mut launcher = format!(
+        "{} -o {} -t {}",
+        LAUNCHER,
+        report_file,
+        limits.wall.as_secs_f64()
+    );
+    if let Some(cpu) = limits.cpu {
+        launcher.push_str(&format!(" -c {}", cpu.as_secs_f64()));
+    }
+    if let Some(memory) = limits.memory {
+        launcher.push_str(&format!(" -m {}", memory));
+    }
+    if let Some(user) = limits.user {
+        launcher.push_str(&format!(" -u {}", user));
+    }
+    format!(
+        "{} -- {}; LAUNCH_RC=$?; printf '\\n%s\\n' {} >&2; cat {} >&2 2>/dev/null; rm -f {}; (exit $LAUNCH_RC)",
+        launcher, cmd, marker, report_file, report_file
+    )
+}
+
+/// 按最后一个 marker 行拆出命令自己的 stderr 与计时，没有计时（如被宿主机超时中断）时为 None
+pub fn split_timing<'a>(stderr: &'a str, marker: &str) -> (&'a str, Option<PhaseTiming>) {
+    let Some(index) = stderr.rfind(&format!("\n{}\n", marker)) else {
+        return (stderr, None);
+    };
+    let fields: Vec<f64> = stderr[index + marker.len() + 2..]
+        .split_whitespace()
+        .filter_map(|field| field.parse().ok())
+        .collect();
+    let timing = match fields[..] {
+        [wall, user, sys] => Some(PhaseTiming {
+            wall_ms: wall * 1000.0,
+            cpu_ms: (user + sys) * 1000.0,
+            peak_memory_kb: 0,
+        }),
+        [wall, user, sys, peak] => Some(PhaseTiming {
+            wall_ms: wall * 1000.0,
+            cpu_ms: (user + sys) * 1000.0,
+            peak_memory_kb: peak as i64,
+        }),
+        _ => None,
+    };
+    (&stderr[..index], timing)
+}
+
+/// 把各阶段耗时写入返回结果的顶层，overhead_ms 为总耗时中其余的部分：
+/// 代码生成、上传文件、Docker 往返与特判等
+pub fn report_timings(response: &mut Value, compile: PhaseTiming, run: PhaseTiming, total_ms: f64) {
+    response["compile_time_ms"] = json!(compile.wall_ms);
+    response["run_wall_time_ms"] = json!(run.wall_ms);
+    response["run_cpu_time_ms"] = json!(run.cpu_ms);
+    response["overhead_ms"] = json!((total_ms - compile.wall_ms - run.wall_ms).max(0.0));
+}
+
+/// coreutils timeout 接受小数秒
+pub fn timeout_arg(limit: Duration) -> String {
+    format!("{}s", limit.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_timing_from_stderr() {
+        let (stderr, timing) = split_timing("warning\n\nm1\n0.250 0.100 0.020\n", "m1");
+        assert_eq!(stderr, "warning\n");
+        let timing = timing.unwrap();
+        assert!((timing.wall_ms - 250.0).abs() < 1e-9);
+        assert!((timing.cpu_ms - 120.0).abs() < 1e-9);
+
+        assert_eq!(timing.peak_memory_kb, 0);
+
+        let (_, timing) = split_timing("\nm1\n0.010 0.004 0.002 2048\n", "m1");
+        assert_eq!(timing.unwrap().peak_memory_kb, 2048);
+
+        assert_eq!(split_timing("killed", "m1"), ("killed", None));
+        assert_eq!(timeout_arg(Duration::from_millis(1500)), "1.5s");
+    }
+}
@@ -1,145 +1,852 @@
 use anyhow::Result;
-use base64::{Engine as _, engine::general_purpose};
-use bollard::exec::{CreateExecOptions, StartExecResults};
-use bytes::BytesMut;
-use futures_util::StreamExt;
-use serde_json::json;
+use serde_json::{Value, json};
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
-use tokio::time::timeout;
 use uuid::Uuid;
 
-use crate::codegen::CodeGenerator;
-use crate::config::Config;
-use crate::docker::client::get_docker;
-use crate::model::request::TestCase;
-use crate::parser::parse_output::parse_output_to_json;
+use crate::codegen::{HarnessSpec, RESULTS_FD};
+use crate::config::{CompileCacheConfig, Config, LanguageConfig, ServerConfig};
+use crate::docker::exec::{ExecOutput, OutputLimits, exec_in_container};
+use crate::docker::sandbox::Sandbox;
+use crate::executor::checker::run_checker;
+use crate::executor::compile_cache::CompileCache;
+use crate::executor::complexity::{Samples, check_cases};
+use crate::executor::timing::{
+    LaunchLimits, PhaseTiming, launched_cmd, report_timings, split_timing, timed_cmd, timeout_arg,
+};
+use crate::model::request::{Checker, ComplexityOptions};
+use crate::model::verdict::{RunFailure, Verdict};
+use crate::parser::diagnostics::{parse_diagnostics, relocate};
+use crate::parser::parse_output::{collect_records, overall_verdict, parse_records};
+use crate::registry::language_registry::LanguageEntry;
 
 pub async fn execute_code(
     lang: &str,
-    code: &str,
-    function: &str,
-    test_cases: &[TestCase],
+    language: &LanguageEntry,
+    spec: &HarnessSpec<'_>,
+    options: &RunOptions,
+    checker: Option<(&LanguageEntry, &Checker)>,
     config: &Config,
-    generator: &dyn CodeGenerator,
-) -> Result<serde_json::Value> {
+) -> Result<Value> {
     let total_start = Instant::now();
-    let docker = get_docker();
-
-    // 验证语言是否启用
-    let lang_config = config
-        .get_language(lang)
-        .ok_or_else(|| anyhow::anyhow!("Language '{}' not supported or disabled", lang))?;
-
-    // 获取容器名称
-    let container_name = config
-        .get_container(lang)
-        .ok_or_else(|| anyhow::anyhow!("Container for '{}' not configured", lang))?;
-
-    let file_id = Uuid::new_v4();
-    let source_file = format!("/tmp/main_{}.{}", file_id, lang_config.file_extension);
-    let binary_file = format!("/tmp/main_{}", file_id);
 
     println!("⏱️  [0ms] Starting {} execution", lang);
 
+    // 校验测试用例与函数签名一致
+    for (index, case) in spec.test_cases.iter().enumerate() {
+        spec.signature.check_case(index, case)?;
+    }
+    spec.comparison.check(&spec.signature.returns)?;
+    if let Some(complexity) = &options.complexity {
+        check_cases(spec.test_cases, complexity)?;
+    }
+    if let Some(benchmark) = spec.benchmark {
+        let total = spec.test_cases.len();
+        let per_process = options.batch_size.map_or(total, |size| size.min(total));
+        benchmark.check(per_process, language.config.run_limit(&config.server))?;
+    }
+
     // 生成测试代码
-    let generated_code = generator.generate(code, function, test_cases)?;
+    let generated_code = language.generator.generate(spec)?;
     println!(
         "⏱️  [{}ms] Code generated",
         total_start.elapsed().as_millis()
     );
 
-    // ===== 使用 Base64 写入文件 =====
-    let encoded_code = general_purpose::STANDARD.encode(&generated_code);
-    let write_cmd = format!("echo '{}' | base64 -d > {}", encoded_code, source_file);
-    // ===============================
+    let lang_config = &language.config;
+    let sandbox = Sandbox::acquire(&language.target, &config.sandbox).await?;
+    let workspace = Workspace::new(lang_config, &sandbox);
 
-    let timeout_cmd = "timeout 5s";
+    // 评测出错或超时都要删除运行目录
+    let judged = judge(
+        &sandbox,
+        &workspace,
+        lang_config,
+        &generated_code,
+        spec,
+        options,
+        config,
+    )
+    .await;
+    workspace.cleanup(&sandbox).await;
+    sandbox.release().await;
+    let (compiled, run) = judged?;
+    println!(
+        "⏱️  [{}ms] Harness finished",
+        total_start.elapsed().as_millis()
+    );
 
-    // 构造完整命令
-    let full_cmd = if let Some(compile_cmd) = &lang_config.compile_cmd {
-        // 编译型语言
-        let compile = compile_cmd
-            .replace("{source}", &source_file)
-            .replace("{binary}", &binary_file);
-        let run = lang_config.run_cmd.replace("{binary}", &binary_file);
-        format!(
-            "{} && {} && {} bash -c '{} 2>&1; EXIT_CODE=$?; rm -f {} {}; exit $EXIT_CODE'",
-            write_cmd, compile, timeout_cmd, run, source_file, binary_file
-        )
+    let mut output = run.output;
+
+    // 特判：由用户提供的 checker 给出每个用例的判定
+    if let Some((checker_language, checker)) = checker {
+        run_checker(checker_language, checker, &mut output, config).await?;
+        println!(
+            "⏱️  [{}ms] Checker finished ({})",
+            total_start.elapsed().as_millis(),
+            checker.language
+        );
+    }
+
+    let verdict = if !compiled.success {
+        Verdict::CompileError
+    } else if run.truncated {
+        Verdict::OutputLimitExceeded
     } else {
-        // 解释型语言
-        let run = lang_config.run_cmd.replace("{source}", &source_file);
-        format!(
-            "{} && {} bash -c '{} 2>&1; EXIT_CODE=$?; rm -f {}; exit $EXIT_CODE'",
-            write_cmd, timeout_cmd, run, source_file
-        )
+        overall_verdict(&output)
     };
 
-    println!(
-        "⏱️  [{}ms] Creating exec",
-        total_start.elapsed().as_millis()
+    let elapsed = total_start.elapsed();
+    println!("⏱️  [{}ms] Completed", elapsed.as_millis());
+
+    let mut response = json!({
+        "language": lang,
+        "verdict": verdict,
+        "execution_time_ms": elapsed.as_millis(),
+        "output": output,
+        "stdout": run.stdout,
+        "stderr": run.stderr
+    });
+    report_timings(
+        &mut response,
+        compiled.timing,
+        run.timing,
+        elapsed.as_secs_f64() * 1000.0,
     );
+    if let Some(report) = compiled.report {
+        response["compile"] = report;
+    }
+    if let Some(complexity) = run.complexity {
+        response["complexity"] = complexity;
+    }
+    Ok(response)
+}
+
+/// 宿主机超时比容器内的 timeout 多出的余量，保证容器内先超时、拿得到退出码
+pub const HOST_GRACE: Duration = Duration::from_secs(2);
+
+/// 删除运行目录的超时时间
+const CLEANUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 读取结果记录的字节上限
+const RECORDS_LIMIT: usize = 16 * 1024 * 1024;
+
+/// 共享容器中运行用户代码的 uid（nobody）
+///
+/// 编译缓存与运行目录都属于 root，用户代码无法改写缓存中的可执行文件或其他运行的文件。
+/// 临时容器去掉了全部 capability，无法切换用户，仍以默认用户运行，它们也不使用编译缓存。
+pub const RUN_UID: u32 = 65534;
+
+/// 请求中决定如何运行用例的选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunOptions {
+    /// 函数模式下每个进程运行的用例数，None 时全部用例在同一进程中运行；stdio 模式不使用
+    pub batch_size: Option<usize>,
+    /// 请求的内存上限（MB）
+    pub memory_limit_mb: Option<u64>,
+    /// 复杂度分析，None 时不分析
+    pub complexity: Option<ComplexityOptions>,
+}
 
-    let exec = docker
-        .create_exec(
-            container_name,
-            CreateExecOptions {
-                cmd: Some(vec!["bash", "-c", &full_cmd]),
-                attach_stdout: Some(true),
-                attach_stderr: Some(true),
-                tty: Some(false),
-                ..Default::default()
+/// 运行阶段的时间、输出与内存限制
+#[derive(Debug, Clone, Copy)]
+pub struct RunLimits {
+    /// 墙钟时限
+    pub time: Duration,
+    /// CPU 时限，None 时不单独限制
+    pub cpu: Option<Duration>,
+    pub output: OutputLimits,
+    pub memory_mb: u64,
+    /// 由启动器用 RLIMIT_AS 执行内存上限，见 [`LanguageConfig::memory_rlimit`]
+    pub address_space: bool,
+}
+
+impl RunLimits {
+    /// 语言的运行限制；语言自己的输出上限不能超过 [server] 中的全局上限
+    pub fn for_language(lang_config: &LanguageConfig, server: &ServerConfig) -> Self {
+        let cap =
+            |language: Option<usize>, global: usize| language.unwrap_or(global).min(global) * 1024;
+        Self {
+            time: lang_config.run_limit(server),
+            cpu: lang_config.cpu_limit(server),
+            output: OutputLimits {
+                stdout: cap(lang_config.stdout_limit_kb, server.stdout_limit_kb),
+                stderr: cap(lang_config.stderr_limit_kb, server.stderr_limit_kb),
             },
+            memory_mb: server.memory_limit_mb,
+            address_space: lang_config.memory_rlimit,
+        }
+    }
+
+    /// 使用请求指定的内存上限，不超过 [server] 中的上限
+    pub fn with_memory_limit(self, requested: Option<u64>) -> Self {
+        Self {
+            memory_mb: requested.map_or(self.memory_mb, |mb| mb.min(self.memory_mb)),
+            ..self
+        }
+    }
+
+    /// 启动器测得的 CPU 时间或内存峰值超过上限时返回对应的异常原因
+    ///
+    /// 内核按整秒执行 CPU 时限，这里按毫秒判定。由 RLIMIT_AS 限制地址空间时峰值不会超过上限，
+    /// 内存超限要由 [`Self::explain`] 从进程的退出方式推断；峰值检查只对设置堆上限的语言起作用。
+    pub fn check(&self, timing: &PhaseTiming) -> Option<RunFailure> {
+        if let Some(cpu) = self
+            .cpu
+            .filter(|cpu| timing.cpu_ms > cpu.as_secs_f64() * 1000.0)
+        {
+            return Some(RunFailure::cpu_limit(timing.cpu_ms, cpu));
+        }
+        (timing.peak_memory_kb > (self.memory_mb * 1024) as i64)
+            .then(|| RunFailure::memory_limit(timing.peak_memory_kb, self.memory_mb))
+    }
+
+    /// 进程异常退出的原因；地址空间受限时，接近上限的崩溃改判为内存超限
+    pub fn explain(&self, failure: RunFailure, timing: &PhaseTiming) -> RunFailure {
+        if self.address_space {
+            failure.or_allocation_failure(timing.peak_memory_kb, self.memory_mb)
+        } else {
+            failure
+        }
+    }
+}
+
+/// 编译的结果
+pub struct Compiled {
+    pub success: bool,
+    /// 编译报告：退出码、编译器输出与解析出的诊断，解释型语言为 None
+    pub report: Option<Value>,
+    /// 容器内测得的编译耗时，解释型语言与命中缓存时为 0
+    pub timing: PhaseTiming,
+}
+
+impl Compiled {
+    /// 编译失败时 output 中的错误信息
+    pub fn error(&self) -> Value {
+        let output = self
+            .report
+            .as_ref()
+            .and_then(|report| report["output"].as_str())
+            .unwrap_or_default();
+        json!({ "error": format!("编译失败:\n{}", output) })
+    }
+}
+
+/// 编译已上传的源码，诊断的行号换算到用户代码 code 中
+///
+/// 语言开启了编译缓存时，先按源码查找缓存，命中则跳过编译。
+pub async fn compile(
+    sandbox: &Sandbox,
+    workspace: &Workspace,
+    lang_config: &LanguageConfig,
+    source: &str,
+    code: &str,
+    cache_config: &CompileCacheConfig,
+) -> Result<Compiled> {
+    // 解释型语言无需编译
+    let Some(compile_cmd) = workspace.compile_cmd(lang_config) else {
+        return Ok(Compiled {
+            success: true,
+            report: None,
+            timing: PhaseTiming::default(),
+        });
+    };
+    let container = sandbox.container.as_str();
+    let binary = &workspace.binary_file;
+    let limit = lang_config.compile_limit();
+
+    let cache = CompileCache::for_source(sandbox, lang_config, cache_config, source).await?;
+    if let Some(cache) = &cache {
+        let hit = exec_in_container(
+            container,
+            &cache.lookup_cmd(binary),
+            None,
+            limit,
+            OutputLimits::INTERNAL,
         )
         .await?;
+        // 摘要与服务端的记录不符时，缓存中的可执行文件被改过，按未命中重新编译
+        let verified = (hit.exit_code == Some(0))
+            .then(|| cache.verify(&hit.stdout))
+            .flatten();
+        if let Some(out) = verified {
+            let hit = ExecOutput {
+                stdout: out.to_string(),
+                ..hit
+            };
+            return Ok(Compiled {
+                success: true,
+                report: Some(compile_report(&hit, &hit.stderr, true, source, code)),
+                timing: PhaseTiming::default(),
+            });
+        }
+    }
 
-    println!(
-        "⏱️  [{}ms] Exec created, starting",
-        total_start.elapsed().as_millis()
-    );
+    let compile_cmd = workspace.timed(&compile_cmd, limit);
+    let cmd = match &cache {
+        Some(cache) => cache.compile_cmd(&compile_cmd, binary),
+        None => compile_cmd,
+    };
+    // 编译器输出由服务端生成的代码决定，使用内部上限
+    let prepared = exec_in_container(
+        container,
+        &cmd,
+        None,
+        limit + HOST_GRACE,
+        OutputLimits::INTERNAL,
+    )
+    .await?;
+    let (stderr, timing) = workspace.split_timing(&prepared.stderr);
+    let success = prepared.exit_code == Some(0);
+    if let Some(cache) = cache.as_ref().filter(|_| success) {
+        // 此时还没有运行用户代码，可执行文件就是刚写入缓存的那份
+        let digest = exec_in_container(
+            container,
+            &cache.digest_cmd(binary),
+            None,
+            limit,
+            OutputLimits::INTERNAL,
+        )
+        .await?;
+        if digest.exit_code == Some(0) {
+            cache.record(&digest.stdout);
+        }
+    }
 
-    let mut buffer = BytesMut::with_capacity(8192);
+    Ok(Compiled {
+        success,
+        report: Some(compile_report(&prepared, stderr, false, source, code)),
+        timing: timing.unwrap_or_default(),
+    })
+}
 
-    let exec_future = async {
-        if let StartExecResults::Attached { mut output, .. } =
-            docker.start_exec(&exec.id, None).await?
-        {
-            while let Some(result) = output.next().await {
-                if let Ok(msg) = result {
-                    use bollard::container::LogOutput;
-                    match msg {
-                        LogOutput::StdOut { message } | LogOutput::StdErr { message } => {
-                            buffer.extend_from_slice(&message);
-                        }
-                        _ => {}
-                    }
-                }
-            }
-        }
-        Ok::<(), anyhow::Error>(())
+fn compile_report(
+    prepared: &ExecOutput,
+    stderr: &str,
+    cached: bool,
+    source: &str,
+    code: &str,
+) -> Value {
+    let output = format!("{}{}", prepared.stdout, stderr);
+    let mut diagnostics = parse_diagnostics(&output);
+    relocate(&mut diagnostics, source, code);
+
+    json!({
+        "exit_code": prepared.exit_code,
+        // 容器内 timeout 超时退出码为 124
+        "timed_out": prepared.timed_out || prepared.exit_code == Some(124),
+        "cached": cached,
+        "output": output,
+        "diagnostics": diagnostics
+    })
+}
+
+/// 上传并编译测试代码，编译成功后运行全部用例
+async fn judge(
+    sandbox: &Sandbox,
+    workspace: &Workspace,
+    lang_config: &LanguageConfig,
+    generated_code: &str,
+    spec: &HarnessSpec<'_>,
+    options: &RunOptions,
+    config: &Config,
+) -> Result<(Compiled, HarnessRun)> {
+    let container = sandbox.container.as_str();
+    workspace.upload(sandbox, generated_code, &[]).await?;
+
+    let compiled = compile(
+        sandbox,
+        workspace,
+        lang_config,
+        generated_code,
+        spec.code,
+        &config.compile_cache,
+    )
+    .await?;
+
+    if !compiled.success {
+        let run = HarnessRun {
+            output: compiled.error(),
+            stdout: String::new(),
+            stderr: String::new(),
+            truncated: false,
+            timing: PhaseTiming::default(),
+            complexity: None,
+        };
+        return Ok((compiled, run));
+    }
+
+    // nonce 只通过 exec 的标准输入传入，不出现在源码和命令行中
+    let nonce = Uuid::new_v4().simple().to_string();
+    let limits = RunLimits::for_language(lang_config, &config.server)
+        .with_memory_limit(options.memory_limit_mb);
+    let mut run = run_cases(
+        container,
+        workspace,
+        lang_config,
+        &nonce,
+        spec,
+        options.batch_size,
+        limits,
+    )
+    .await?;
+
+    if let Some(complexity) = &options.complexity {
+        // 崩溃或超限的用例拿不到样本，拟合结果没有意义
+        let failed = run.truncated
+            || !matches!(
+                overall_verdict(&run.output),
+                Verdict::Accepted | Verdict::WrongAnswer
+            );
+        run.complexity = Some(if failed {
+            json!({ "error": "存在运行失败的用例，未进行复杂度分析" })
+        } else {
+            analyze_complexity(
+                container,
+                workspace,
+                lang_config,
+                &nonce,
+                spec,
+                &limits,
+                complexity,
+            )
+            .await?
+        });
+    }
+    Ok((compiled, run))
+}
+
+/// 测试代码运行后的评测结果与用户代码自己的输出
+struct HarnessRun {
+    output: Value,
+    stdout: String,
+    stderr: String,
+    /// stdout 或 stderr 超过上限被截断
+    truncated: bool,
+    /// 各批次的运行耗时之和
+    timing: PhaseTiming,
+    /// 复杂度分析的结果，未请求时为 None
+    complexity: Option<Value>,
+}
+
+/// 运行编译好的测试代码
+///
+/// batch_size 为 None 时全部用例在同一进程中运行，进程异常退出后剩余用例不再运行；
+/// 否则每批用例单独启动一个进程，异常原因记在第一个没有结果的用例上，其后的用例在新进程中继续运行。
+/// 输出上限对所有批次合计，超出后不再运行剩余批次；CPU 时限与内存上限对每个进程单独计算。
+async fn run_cases(
+    container: &str,
+    workspace: &Workspace,
+    lang_config: &LanguageConfig,
+    nonce: &str,
+    spec: &HarnessSpec<'_>,
+    batch_size: Option<usize>,
+    limits: RunLimits,
+) -> Result<HarnessRun> {
+    let total = spec.test_cases.len();
+    let mut run = HarnessRun {
+        output: Value::Null,
+        stdout: String::new(),
+        stderr: String::new(),
+        truncated: false,
+        timing: PhaseTiming::default(),
+        complexity: None,
     };
+    let mut outputs = HashMap::new();
+    let mut failures = HashMap::new();
+    let mut first = 0;
 
-    // Rust层超时保护
-    let result = timeout(Duration::from_secs(config.server.run_timeout), exec_future).await;
+    while first < total {
+        let last = batch_size.map_or(total, |size| (first + size).min(total));
+        let range = batch_size.map(|_| (first, last));
+        // 各批次共用输出上限，本批只能使用剩余的部分
+        let output_limits = OutputLimits {
+            stdout: limits.output.stdout.saturating_sub(run.stdout.len()),
+            stderr: limits.output.stderr.saturating_sub(run.stderr.len()),
+        };
+        let batch = run_batch(
+            container,
+            workspace,
+            lang_config,
+            nonce,
+            range,
+            &limits,
+            output_limits,
+        )
+        .await?;
 
-    let elapsed = total_start.elapsed().as_millis();
+        let timing = batch.timing;
+        run.stdout.push_str(&batch.exec.stdout);
+        run.stderr.push_str(&batch.stderr);
+        run.timing += timing;
+        // 截断一旦发生就保持，之后的批次不能把它清掉
+        run.truncated |= batch.exec.truncated();
 
-    let output_text = match result {
-        Ok(inner) => {
-            if let Err(e) = inner {
-                format!("❌ Internal error: {}", e)
+        // 超过 CPU 时限或内存上限的批次整体作废，其中每个用例都使用同一判定
+        if let Some(failure) = limits.check(&timing) {
+            for index in first..last {
+                failures.insert(index, failure.clone());
+            }
+            first = if batch_size.is_some() && !run.truncated {
+                last
             } else {
-                String::from_utf8_lossy(&buffer).to_string()
+                total
+            };
+            continue;
+        }
+
+        let records = collect_records(&batch.records, nonce, first..last);
+        // 记录被篡改的批次同样整体作废
+        if records.tampered {
+            for index in first..last {
+                failures.insert(index, RunFailure::tampered());
             }
+            first = if run.truncated { total } else { last };
+            continue;
         }
-        Err(_) => "⏰ Execution timed out (killed by host)".to_string(),
-    };
+        let missing = (first..last).find(|i| !records.cases.contains_key(i));
+        outputs.extend(records.cases);
+        first = match missing {
+            Some(index) => {
+                let failure = if batch.exec.stdout_truncated {
+                    RunFailure::output_limit("stdout", limits.output.stdout)
+                } else if batch.exec.stderr_truncated {
+                    RunFailure::output_limit("stderr", limits.output.stderr)
+                } else {
+                    RunFailure::from_exit(batch.exec.exit_code, batch.exec.timed_out, &batch.stderr)
+                        .map(|failure| limits.explain(failure, &timing))
+                        .unwrap_or_else(RunFailure::early_exit)
+                };
+                failures.insert(index, failure);
+                if batch_size.is_some() && !run.truncated {
+                    index + 1
+                } else {
+                    total
+                }
+            }
+            None if run.truncated => total,
+            None => last,
+        };
+    }
 
-    println!("⏱️  [{}ms] Completed", elapsed);
+    run.output = parse_records(outputs, spec, &failures, run.timing.peak_memory_kb);
+    Ok(run)
+}
 
-    Ok(json!({
-        "language": lang,
-        "execution_time_ms": elapsed,
-        "output": parse_output_to_json(&output_text)
-    }))
+/// 复杂度分析：每个用例单独启动进程并重复 repeats 次，
+/// 按用例的 size 汇总启动器测得的进程 CPU 时间与内存峰值
+///
+/// 不使用测试代码记录的 time_ms：它与用户代码在同一进程中，可以被改写。
+/// 进程启动开销对各规模相同，由拟合的截距吸收。
+async fn analyze_complexity(
+    container: &str,
+    workspace: &Workspace,
+    lang_config: &LanguageConfig,
+    nonce: &str,
+    spec: &HarnessSpec<'_>,
+    limits: &RunLimits,
+    options: &ComplexityOptions,
+) -> Result<Value> {
+    let mut samples = Samples::default();
+
+    for _ in 0..options.repeats {
+        for (index, case) in spec.test_cases.iter().enumerate() {
+            let Some(size) = case.size else {
+                continue;
+            };
+            let batch = run_batch(
+                container,
+                workspace,
+                lang_config,
+                nonce,
+                Some((index, index + 1)),
+                limits,
+                limits.output,
+            )
+            .await?;
+            // 宿主机超时（没有启动器计时）、超限或没有记录的运行不计入样本
+            if batch.exec.timed_out || limits.check(&batch.timing).is_some() {
+                continue;
+            }
+            let records = collect_records(&batch.records, nonce, index..index + 1);
+            if !records.tampered && records.cases.contains_key(&index) {
+                samples.add(size, batch.timing.cpu_ms, batch.timing.peak_memory_kb);
+            }
+        }
+    }
+
+    Ok(samples.report(options.repeats))
+}
+
+/// 一个批次的运行结果
+struct Batch {
+    exec: ExecOutput,
+    /// 拆出计时后用户代码自己的 stderr
+    stderr: String,
+    records: String,
+    timing: PhaseTiming,
+}
+
+/// 运行一批用例，再单独读取它写下的结果记录
+async fn run_batch(
+    container: &str,
+    workspace: &Workspace,
+    lang_config: &LanguageConfig,
+    nonce: &str,
+    range: Option<(usize, usize)>,
+    limits: &RunLimits,
+    output_limits: OutputLimits,
+) -> Result<Batch> {
+    let cmd = format!(
+        "read -r NONCE; {}",
+        harness_cmd(workspace, lang_config, range, limits)
+    );
+    let exec = exec_in_container(
+        container,
+        &cmd,
+        Some(format!("{}\n", nonce).as_bytes()),
+        limits.time + HOST_GRACE,
+        output_limits,
+    )
+    .await?;
+    // 结果记录单独读取，不占用用户的输出上限
+    let read_records = format!("cat {0} 2>/dev/null; rm -f {0}", workspace.results_file);
+    let records = exec_in_container(
+        container,
+        &read_records,
+        None,
+        CLEANUP_TIMEOUT,
+        OutputLimits {
+            stdout: RECORDS_LIMIT,
+            stderr: OutputLimits::INTERNAL.stderr,
+        },
+    )
+    .await?
+    .stdout;
+
+    let (stderr, timing) = workspace.split_timing(&exec.stderr);
+    Ok(Batch {
+        stderr: stderr.to_string(),
+        timing: timing.unwrap_or_default(),
+        exec,
+        records,
+    })
+}
+
+/// 运行测试代码的命令：程序先从标准输入读取 nonce，结果写入文件描述符 3 对应的文件，
+/// 运行结束后由单独的命令读取
+///
+/// range 为 `(first, last)` 时只运行下标在 `[first, last)` 内的用例。
+fn harness_cmd(
+    workspace: &Workspace,
+    lang_config: &LanguageConfig,
+    range: Option<(usize, usize)>,
+    limits: &RunLimits,
+) -> String {
+    let mut run_cmd = workspace.run_cmd(lang_config, limits);
+    if let Some((first, last)) = range {
+        run_cmd.push_str(&format!(" {} {}", first, last));
+    }
+    // 重定向由启动器外的 shell 打开，运行用户代码的 uid 不需要写运行目录的权限
+    let harness = format!(
+        "bash -c '{}' {}>{} <<< \"$NONCE\"",
+        run_cmd, RESULTS_FD, workspace.results_file
+    );
+
+    workspace.launched(&harness, lang_config, limits)
+}
+
+/// 一次运行在容器内使用的独立目录，源码、可执行文件与结果文件都放在其中，
+/// 并发运行互不覆盖，结束后整个目录一起删除
+pub struct Workspace {
+    pub dir: String,
+    /// 源码在目录中的文件名
+    source_name: String,
+    pub source_file: String,
+    pub binary_file: String,
+    /// 测试代码写入结果记录的文件
+    pub results_file: String,
+    /// 分隔 stderr 与计时的标记
+    timing_marker: String,
+    /// 启动器运行用户代码的 uid，见 RUN_UID
+    run_as: Option<u32>,
+}
+
+impl Workspace {
+    pub fn new(lang_config: &LanguageConfig, sandbox: &Sandbox) -> Self {
+        let dir = format!("/tmp/main_{}", Uuid::new_v4());
+        // 文件名固定的语言（如 Java）使用配置的文件名
+        let name = lang_config.source_name.as_deref().unwrap_or("main");
+        let source_name = format!("{}.{}", name, lang_config.file_extension);
+        Self {
+            source_file: format!("{}/{}", dir, source_name),
+            binary_file: format!("{}/{}", dir, name),
+            results_file: format!("{}/results.jsonl", dir),
+            timing_marker: Uuid::new_v4().simple().to_string(),
+            run_as: (!sandbox.is_ephemeral()).then_some(RUN_UID),
+            source_name,
+            dir,
+        }
+    }
+
+    /// 目录中某个文件的完整路径
+    pub fn path(&self, name: &str) -> String {
+        format!("{}/{}", self.dir, name)
+    }
+
+    /// 以 tar 流上传源码与附加文件，附加文件名相对于运行目录
+    pub async fn upload(
+        &self,
+        sandbox: &Sandbox,
+        source: &str,
+        files: &[(String, &str)],
+    ) -> Result<()> {
+        let mut entries = vec![(self.source_name.as_str(), source.as_bytes())];
+        entries.extend(
+            files
+                .iter()
+                .map(|(name, content)| (name.as_str(), content.as_bytes())),
+        );
+        sandbox.upload(&self.dir, &entries).await
+    }
+
+    /// 计时并限时运行简单命令，计时写到 stderr 末尾
+    pub fn timed(&self, cmd: &str, limit: Duration) -> String {
+        timed_cmd(cmd, limit, &self.timing_marker, &self.path("timing"))
+    }
+
+    /// 由启动器运行简单命令，测得的时间与内存峰值写到 stderr 末尾，同样由 split_timing 拆出
+    ///
+    /// 共享容器中命令以 RUN_UID 运行，cmd 末尾的重定向仍由启动器外的 shell 以 root 打开。
+    pub fn launched(&self, cmd: &str, lang_config: &LanguageConfig, limits: &RunLimits) -> String {
+        let launch_limits = LaunchLimits {
+            wall: limits.time,
+            cpu: limits.cpu,
+            memory: lang_config
+                .memory_rlimit
+                .then_some(limits.memory_mb * 1024 * 1024),
+            user: self.run_as,
+        };
+        launched_cmd(
+            cmd,
+            &launch_limits,
+            &self.timing_marker,
+            &self.path("timing"),
+        )
+    }
+
+    /// 拆出 timed 命令自己的 stderr 与计时
+    pub fn split_timing<'a>(&self, stderr: &'a str) -> (&'a str, Option<PhaseTiming>) {
+        split_timing(stderr, &self.timing_marker)
+    }
+
+    /// 编译命令，解释型语言为 None
+    pub fn compile_cmd(&self, lang_config: &LanguageConfig) -> Option<String> {
+        lang_config
+            .compile_cmd
+            .as_ref()
+            .map(|cmd| self.substitute(cmd))
+    }
+
+    /// 运行命令，{memory_mb} 替换为内存上限
+    pub fn run_cmd(&self, lang_config: &LanguageConfig, limits: &RunLimits) -> String {
+        self.substitute(&lang_config.run_cmd)
+            .replace("{memory_mb}", &limits.memory_mb.to_string())
+    }
+
+    /// 删除运行目录，清理失败不影响评测结果；临时容器随后整个删除，无需清理
+    pub async fn cleanup(&self, sandbox: &Sandbox) {
+        if sandbox.is_ephemeral() {
+            return;
+        }
+        let cmd = format!("rm -rf {}", self.dir);
+        let output = exec_in_container(
+            &sandbox.container,
+            &cmd,
+            None,
+            CLEANUP_TIMEOUT,
+            OutputLimits::INTERNAL,
+        )
+        .await;
+        if let Err(e) = output {
+            eprintln!("⚠️  Failed to clean up {}: {}", self.dir, e);
+        }
+    }
+
+    fn substitute(&self, cmd: &str) -> String {
+        cmd.replace("{source}", &self.source_file)
+            .replace("{binary}", &self.binary_file)
+            .replace("{workdir}", &self.dir)
+    }
+}
+
+/// 在语言对应的容器中上传源码、编译并运行，stdout 与 stderr 分开返回
+///
+/// stdin 不为空时作为文件一并上传，重定向为程序的标准输入。
+/// 程序与评测代码一样由启动器运行，共享容器中同样以 RUN_UID 运行。
+/// 编译错误写在 stderr 中；宿主机超时时 timed_out 为 true。
+pub async fn run_program(
+    language: &LanguageEntry,
+    source: &str,
+    stdin: Option<&str>,
+    config: &Config,
+) -> Result<ExecOutput> {
+    let total_start = Instant::now();
+    let lang_config = &language.config;
+    let limits = RunLimits::for_language(lang_config, &config.server);
+
+    println!(
+        "⏱️  [{}ms] Creating exec",
+        total_start.elapsed().as_millis()
+    );
+
+    let sandbox = Sandbox::acquire(&language.target, &config.sandbox).await?;
+    let workspace = Workspace::new(lang_config, &sandbox);
+
+    // 构造完整命令
+    let mut run_cmd = format!("bash -c '{}'", workspace.run_cmd(lang_config, &limits));
+    let mut files = Vec::new();
+    if let Some(stdin) = stdin {
+        files.push(("stdin.txt".to_string(), stdin));
+        run_cmd.push_str(&format!(" < {}", workspace.path("stdin.txt")));
+    }
+    let mut full_cmd = workspace.launched(&run_cmd, lang_config, &limits);
+    let mut host_timeout = limits.time + HOST_GRACE;
+    if let Some(compile) = workspace.compile_cmd(lang_config) {
+        // 编译型语言
+        let compile_limit = lang_config.compile_limit();
+        full_cmd = format!(
+            "timeout {} {} && {{ {}; }}",
+            timeout_arg(compile_limit),
+            compile,
+            full_cmd
+        );
+        host_timeout += compile_limit;
+    }
+
+    let result = async {
+        workspace.upload(&sandbox, source, &files).await?;
+        let mut output = exec_in_container(
+            &sandbox.container,
+            &full_cmd,
+            None,
+            host_timeout,
+            limits.output,
+        )
+        .await?;
+        // 只保留程序自己的 stderr，编译失败时没有计时
+        output.stderr = workspace.split_timing(&output.stderr).0.to_string();
+        Ok(output)
+    }
+    .await;
+    // 清理单独执行，宿主机超时后也能删除运行目录
+    workspace.cleanup(&sandbox).await;
+    sandbox.release().await;
+
+    println!(
+        "⏱️  [{}ms] Exec finished",
+        total_start.elapsed().as_millis()
+    );
+
+    result
 }
@@ -1,4 +1,7 @@
-use axum::{Router, routing::post};
+use axum::{
+    Router,
+    routing::{get, post},
+};
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 
@@ -9,8 +12,11 @@ mod docker;
 mod executor;
 mod model;
 mod parser;
+mod registry;
 
+use api::AppState;
 use config::Config;
+use registry::language_registry::LanguageRegistry;
 
 #[tokio::main]
 async fn main() {
@@ -21,37 +27,38 @@ async fn main() {
     // 初始化 Docker 客户端
     docker::client::init_docker(&config).expect("Docker 客户端初始化失败");
 
+    // 构建语言注册表，启用的语言缺少生成器或容器时拒绝启动
+    let registry = LanguageRegistry::from_config(&config).expect("语言注册失败");
+
     // 打印启用的语言
     println!("\n📚 Enabled languages:");
-    for (lang, cfg) in &config.languages {
-        if cfg.enabled
-            && let Some(container) = config.containers.get(lang)
-        {
-            println!(
-                "  - {}: {} (container: {})",
-                lang, cfg.file_extension, container
-            );
-        }
+    for (lang, entry) in registry.iter() {
+        println!(
+            "  - {}: {} ({})",
+            lang, entry.config.file_extension, entry.target
+        );
     }
 
-    let config = Arc::new(config);
+    // 预热临时容器
+    docker::pool::init_pool(&config, &registry);
+
+    // 定期删除超时或清理失败后遗留的运行目录
+    docker::reaper::spawn_reaper(&config);
+
     let bind_addr = config.server.bind_addr.clone();
+    let state = Arc::new(AppState { config, registry });
 
     let cors = CorsLayer::new()
         .allow_origin(Any) // 允许所有来源
         .allow_methods(Any) // 允许所有方法（GET, POST 等）
         .allow_headers(Any); // 允许所有头部
 
-    // 创建路由，传递配置
+    // 创建路由，通过 State 共享配置与语言注册表
     let app = Router::new()
-        .route(
-            "/run",
-            post({
-                let config = Arc::clone(&config);
-                move |body| api::run_code::run_code(Arc::clone(&config), body)
-            }),
-        )
-        .layer(cors);
+        .route("/run", post(api::run_code::run_code))
+        .route("/pool", get(api::pool::pool_stats))
+        .layer(cors)
+        .with_state(state);
 
     println!("\n🚀 Server running on http://{}", bind_addr);
     println!("📦 Ready to execute code\n");
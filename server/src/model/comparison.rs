@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::signature::ValueType;
+
+/// 返回值与期望值的比较方式，JSON 中写作 `{"mode": "epsilon", "abs": 1e-6}` 等，省略时为精确比较
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Comparison {
+    /// 精确相等
+    #[default]
+    Exact,
+    /// 浮点数误差：|a - b| <= abs 或 |a - b| <= rel * |b|
+    Epsilon {
+        #[serde(default = "default_abs")]
+        abs: f64,
+        #[serde(default)]
+        rel: f64,
+    },
+    /// 数组忽略顺序，元素个数需一致
+    Unordered,
+    /// 数组忽略顺序与重复元素
+    Set,
+    /// 字符串忽略大小写
+    CaseInsensitive,
+}
+
+fn default_abs() -> f64 {
+    1e-6
+}
+
+impl Comparison {
+    /// 校验比较方式是否适用于返回值类型
+    pub fn check(&self, returns: &ValueType) -> anyhow::Result<()> {
+        let ok = match self {
+            Comparison::Exact => true,
+            Comparison::Epsilon { .. } => *returns.scalar() == ValueType::Double,
+            Comparison::Unordered | Comparison::Set => !returns.is_scalar(),
+            Comparison::CaseInsensitive => *returns == ValueType::String,
+        };
+
+        if ok {
+            Ok(())
+        } else {
+            anyhow::bail!("比较方式 {} 不适用于返回值类型 {}", self.name(), returns)
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Comparison::Exact => "exact",
+            Comparison::Epsilon { .. } => "epsilon",
+            Comparison::Unordered => "unordered",
+            Comparison::Set => "set",
+            Comparison::CaseInsensitive => "case_insensitive",
+        }
+    }
+}
+
+/// stdio 模式下标准输出与期望输出的比较方式
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Whitespace {
+    /// 逐字节相等
+    Exact,
+    /// 统一换行符为 \n，忽略每行行尾空白与末尾空行
+    #[default]
+    TrimLines,
+    /// 按空白切分后逐个比较
+    Tokens,
+}
+
+impl Whitespace {
+    pub fn matches(&self, output: &str, expected: &str) -> bool {
+        match self {
+            Whitespace::Exact => output == expected,
+            Whitespace::TrimLines => trim_lines(output) == trim_lines(expected),
+            Whitespace::Tokens => output.split_whitespace().eq(expected.split_whitespace()),
+        }
+    }
+}
+
+fn trim_lines(text: &str) -> Vec<&str> {
+    let mut lines = text.lines().map(str::trim_end).collect::<Vec<_>>();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_modes() {
+        let output = "1 2\r\n3  \n\n";
+
+        assert!(!Whitespace::Exact.matches(output, "1 2\n3\n"));
+        assert!(Whitespace::TrimLines.matches(output, "1 2\n3"));
+        assert!(!Whitespace::TrimLines.matches(output, "1  2\n3"));
+        assert!(Whitespace::Tokens.matches(output, "1\n2 3"));
+    }
+}
@@ -1 +1,4 @@
+pub mod comparison;
 pub mod request;
+pub mod signature;
+pub mod verdict;
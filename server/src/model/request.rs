@@ -1,15 +1,174 @@
 use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::model::comparison::{Comparison, Whitespace};
+use crate::model::signature::Signature;
 
 #[derive(Deserialize)]
 pub struct TestCase {
-    pub input: Vec<i32>,
-    pub expected: i32,
+    pub input: Vec<Value>,
+    pub expected: Value,
+    /// 输入规模 n，复杂度分析按它拟合
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+/// stdio 模式的用例：标准输入文本与期望的标准输出文本
+#[derive(Deserialize)]
+pub struct StdioCase {
+    pub input: String,
+    pub expected: String,
+}
+
+/// 评测模式
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunMode {
+    /// 生成测试代码逐个调用 function
+    #[default]
+    Function,
+    /// 完整程序从标准输入读取、向标准输出写入
+    Stdio,
+}
+
+/// 复杂度分析：按用例的 size 分别运行，拟合耗时与内存随规模的增长
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ComplexityOptions {
+    /// 每个用例重复运行的次数，取中位数
+    #[serde(default = "default_repeats")]
+    pub repeats: usize,
+}
+
+fn default_repeats() -> usize {
+    3
+}
+
+/// 基准测试：每个用例先预热，再反复调用直到累计测量时间达到 min_time_ms
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct BenchmarkOptions {
+    /// 预热时间（毫秒）
+    #[serde(default = "default_warmup_ms")]
+    pub warmup_ms: f64,
+    /// 每个用例的最少测量时间（毫秒）
+    #[serde(default = "default_min_time_ms")]
+    pub min_time_ms: f64,
+}
+
+fn default_warmup_ms() -> f64 {
+    20.0
+}
+
+fn default_min_time_ms() -> f64 {
+    100.0
+}
+
+impl BenchmarkOptions {
+    /// 校验时间设置，并确认一个进程中的 cases 个用例测完不会超过运行时限
+    ///
+    /// 预热每轮调用次数翻倍，最后一轮可能让预热用到约 2 倍的 warmup_ms。
+    pub fn check(&self, cases: usize, run_limit: Duration) -> anyhow::Result<()> {
+        if !(self.warmup_ms >= 0.0 && self.min_time_ms > 0.0) {
+            anyhow::bail!("benchmark.warmup_ms 不能为负，min_time_ms 必须大于 0");
+        }
+        let estimated = (self.warmup_ms * 2.0 + self.min_time_ms) * cases as f64;
+        let limit = run_limit.as_secs_f64() * 1000.0;
+        if estimated >= limit {
+            anyhow::bail!(
+                "基准测试预计耗时 {:.0} ms，超过运行时限 {:.0} ms，请减少用例、设置 batch_size 或缩短测量时间",
+                estimated,
+                limit
+            );
+        }
+        Ok(())
+    }
+}
+
+/// 特判程序：从标准输入逐行读取用例，逐行输出判定
+#[derive(Deserialize)]
+pub struct Checker {
+    pub language: String,
+    pub code: String,
 }
 
 #[derive(Deserialize)]
 pub struct CodeRequest {
     pub language: String, // 指定语言
     pub code: String,
+    /// 评测模式，省略时为函数调用
+    #[serde(default)]
+    pub mode: RunMode,
+    /// 被测函数名，stdio 模式下不需要
+    #[serde(default)]
     pub function: String,
-    pub test_cases: Vec<TestCase>,
-}
\ No newline at end of file
+    /// 函数签名，省略时所有参数与返回值按 int 处理
+    #[serde(default)]
+    pub signature: Option<Signature>,
+    /// 返回值的比较方式，省略时为精确比较
+    #[serde(default)]
+    pub comparison: Comparison,
+    /// stdio 模式下标准输出的比较方式，省略时忽略行尾空白与末尾空行
+    #[serde(default)]
+    pub whitespace: Whitespace,
+    /// 特判程序，设置后用例是否通过由它决定
+    #[serde(default)]
+    pub checker: Option<Checker>,
+    /// 函数模式下每个进程运行的用例数，省略时全部用例在同一进程中运行
+    #[serde(default)]
+    pub batch_size: Option<usize>,
+    /// 运行时的内存上限（MB），省略时使用配置中的上限，超过配置时按配置截断
+    #[serde(default)]
+    pub memory_limit_mb: Option<u64>,
+    /// 复杂度分析，省略时不分析；仅支持函数模式，每个用例都要设置 size
+    #[serde(default)]
+    pub complexity: Option<ComplexityOptions>,
+    /// 基准测试，省略时每个用例只调用一次；支持 C、C++、Python 的函数模式
+    #[serde(default)]
+    pub benchmark: Option<BenchmarkOptions>,
+    /// 函数模式为 TestCase，stdio 模式为 StdioCase
+    pub test_cases: Vec<Value>,
+}
+
+impl CodeRequest {
+    pub fn function_cases(&self) -> anyhow::Result<Vec<TestCase>> {
+        parse_cases(&self.test_cases)
+    }
+
+    pub fn stdio_cases(&self) -> anyhow::Result<Vec<StdioCase>> {
+        parse_cases(&self.test_cases)
+    }
+
+    pub fn resolved_signature(&self) -> Signature {
+        self.signature.clone().unwrap_or_else(|| {
+            let arity = self
+                .test_cases
+                .first()
+                .and_then(|t| t.get("input"))
+                .and_then(Value::as_array)
+                .map_or(0, Vec::len);
+            Signature::all_int(arity)
+        })
+    }
+}
+
+fn parse_cases<T: DeserializeOwned>(cases: &[Value]) -> anyhow::Result<Vec<T>> {
+    cases
+        .iter()
+        .enumerate()
+        .map(|(index, case)| {
+            T::deserialize(case).map_err(|e| anyhow::anyhow!("用例 {} 格式错误: {}", index, e))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+impl TestCase {
+    pub fn ints(input: &[i64], expected: i64) -> Self {
+        Self {
+            input: input.iter().map(|&x| Value::from(x)).collect(),
+            expected: Value::from(expected),
+            size: None,
+        }
+    }
+}
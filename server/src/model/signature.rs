@@ -0,0 +1,183 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::fmt;
+
+use crate::model::request::TestCase;
+
+/// 参数或返回值类型，JSON 中写作 "int"、"long"、"double"、"bool"、"string"，
+/// 数组在末尾追加 "[]"，如 "int[]"、"string[]"、"int[][]"；
+/// "ListNode" / "TreeNode" 为 LeetCode 风格的链表与二叉树，值用层序数组表示，如 [1,null,2,3]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ValueType {
+    Int,
+    Long,
+    Double,
+    Bool,
+    String,
+    List,
+    Tree,
+    Array(Box<ValueType>),
+}
+
+impl ValueType {
+    pub fn is_node(&self) -> bool {
+        matches!(self, ValueType::List | ValueType::Tree)
+    }
+
+    /// 比较与展示用的规范形式：二叉树去掉层序数组末尾的 null
+    pub fn canonical(&self, value: &Value) -> Value {
+        match (self, value) {
+            (ValueType::Tree, Value::Array(items)) => {
+                let len = items.len() - items.iter().rev().take_while(|v| v.is_null()).count();
+                Value::Array(items[..len].to_vec())
+            }
+            _ => value.clone(),
+        }
+    }
+
+    pub fn is_scalar(&self) -> bool {
+        !matches!(self, ValueType::Array(_))
+    }
+
+    /// 数组嵌套层数，标量为 0
+    pub fn depth(&self) -> usize {
+        match self {
+            ValueType::Array(inner) => 1 + inner.depth(),
+            _ => 0,
+        }
+    }
+
+    /// 最内层的标量类型
+    pub fn scalar(&self) -> &ValueType {
+        match self {
+            ValueType::Array(inner) => inner.scalar(),
+            scalar => scalar,
+        }
+    }
+
+    /// 校验 JSON 值是否符合该类型
+    pub fn check(&self, value: &Value) -> anyhow::Result<()> {
+        let ok = match self {
+            ValueType::Int => value.as_i64().is_some_and(|v| i32::try_from(v).is_ok()),
+            ValueType::Long => value.is_i64(),
+            ValueType::Double => value.is_number(),
+            ValueType::Bool => value.is_boolean(),
+            ValueType::String => value.is_string(),
+            ValueType::List => value
+                .as_array()
+                .is_some_and(|items| items.iter().all(|v| ValueType::Int.check(v).is_ok())),
+            ValueType::Tree => value.as_array().is_some_and(|items| {
+                items.first().is_none_or(|root| !root.is_null())
+                    && items
+                        .iter()
+                        .all(|v| v.is_null() || ValueType::Int.check(v).is_ok())
+            }),
+            ValueType::Array(inner) => match value.as_array() {
+                Some(items) => {
+                    for item in items {
+                        inner.check(item)?;
+                    }
+                    true
+                }
+                None => false,
+            },
+        };
+
+        if ok {
+            Ok(())
+        } else {
+            anyhow::bail!("值 {} 不是 {} 类型", value, self)
+        }
+    }
+}
+
+impl TryFrom<String> for ValueType {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        let name = name.trim();
+        if let Some(inner) = name.strip_suffix("[]") {
+            let inner = ValueType::try_from(inner.to_string())?;
+            if inner.scalar().is_node() {
+                return Err(format!("{} 不能作为数组元素", inner.scalar()));
+            }
+            return Ok(ValueType::Array(Box::new(inner)));
+        }
+
+        match name {
+            "int" | "i32" => Ok(ValueType::Int),
+            "long" | "i64" => Ok(ValueType::Long),
+            "double" | "float" | "f64" => Ok(ValueType::Double),
+            "bool" | "boolean" => Ok(ValueType::Bool),
+            "string" | "str" => Ok(ValueType::String),
+            "ListNode" | "list" => Ok(ValueType::List),
+            "TreeNode" | "tree" => Ok(ValueType::Tree),
+            _ => Err(format!("未知类型: {}", name)),
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueType::Int => write!(f, "int"),
+            ValueType::Long => write!(f, "long"),
+            ValueType::Double => write!(f, "double"),
+            ValueType::Bool => write!(f, "bool"),
+            ValueType::String => write!(f, "string"),
+            ValueType::List => write!(f, "ListNode"),
+            ValueType::Tree => write!(f, "TreeNode"),
+            ValueType::Array(inner) => write!(f, "{}[]", inner),
+        }
+    }
+}
+
+/// 被测函数的签名
+#[derive(Debug, Clone, Deserialize)]
+pub struct Signature {
+    pub params: Vec<ValueType>,
+    pub returns: ValueType,
+}
+
+impl Signature {
+    /// 未声明签名时沿用旧行为：所有参数与返回值均为 int
+    pub fn all_int(arity: usize) -> Self {
+        Self {
+            params: vec![ValueType::Int; arity],
+            returns: ValueType::Int,
+        }
+    }
+
+    /// 参数或返回值中是否出现该类型（含数组元素）
+    pub fn uses(&self, ty: &ValueType) -> bool {
+        self.params
+            .iter()
+            .chain([&self.returns])
+            .any(|t| t.scalar() == ty)
+    }
+
+    pub fn is_all_int(&self) -> bool {
+        self.returns == ValueType::Int && self.params.iter().all(|p| *p == ValueType::Int)
+    }
+
+    /// 校验测试用例的参数个数与类型
+    pub fn check_case(&self, index: usize, case: &TestCase) -> anyhow::Result<()> {
+        if case.input.len() != self.params.len() {
+            anyhow::bail!(
+                "测试用例 {} 的参数个数为 {}，函数签名需要 {} 个",
+                index,
+                case.input.len(),
+                self.params.len()
+            );
+        }
+
+        for (i, (ty, value)) in self.params.iter().zip(&case.input).enumerate() {
+            ty.check(value)
+                .map_err(|e| anyhow::anyhow!("测试用例 {} 的第 {} 个参数: {}", index, i + 1, e))?;
+        }
+        self.returns
+            .check(&case.expected)
+            .map_err(|e| anyhow::anyhow!("测试用例 {} 的期望值: {}", index, e))
+    }
+}
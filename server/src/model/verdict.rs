@@ -0,0 +1,264 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// 用例或整次提交的判定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Accepted,
+    WrongAnswer,
+    CompileError,
+    RuntimeError,
+    TimeLimitExceeded,
+    MemoryLimitExceeded,
+    OutputLimitExceeded,
+}
+
+impl Verdict {
+    pub fn from_passed(passed: bool) -> Self {
+        if passed {
+            Verdict::Accepted
+        } else {
+            Verdict::WrongAnswer
+        }
+    }
+}
+
+/// 错误信息中保留的 stderr 长度
+const STDERR_LIMIT: usize = 1024;
+
+/// 内存耗尽时各语言运行时在 stderr 中留下的特征
+const OOM_MARKERS: [&str; 5] = [
+    "MemoryError",
+    "std::bad_alloc",
+    "OutOfMemoryError",
+    "out of memory",
+    "memory allocation of",
+];
+
+/// 分配失败后进程常见的终止信号：C 的 malloc 返回 NULL 后解引用（SIGSEGV、SIGBUS），或运行时直接 abort
+const ALLOCATION_FAILURE_SIGNALS: [i32; 3] = [6, 7, 11];
+
+/// 进程异常退出的原因，记在第一个没有产生结果的用例上
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunFailure {
+    pub verdict: Verdict,
+    pub exit_code: Option<i64>,
+    /// 终止进程的信号
+    pub signal: Option<i32>,
+    pub message: String,
+}
+
+impl RunFailure {
+    /// 由退出码判断进程是否异常退出，正常退出时返回 None
+    ///
+    /// 退出码按 bash 的约定解释：`timeout` 超时为 124，被信号终止为 128 + 信号值。
+    pub fn from_exit(exit_code: Option<i64>, timed_out: bool, stderr: &str) -> Option<Self> {
+        let stderr = truncate(stderr, STDERR_LIMIT);
+
+        let mut failure = match exit_code {
+            _ if timed_out => Self::new(Verdict::TimeLimitExceeded, None, "运行超时".to_string()),
+            Some(0) => return None,
+            Some(124) => Self::new(Verdict::TimeLimitExceeded, None, "运行超时".to_string()),
+            // 超过 RLIMIT_CPU 后内核发送 SIGXCPU
+            Some(152) => Self::new(
+                Verdict::TimeLimitExceeded,
+                Some(24),
+                "CPU 时间超限".to_string(),
+            ),
+            Some(code) if OOM_MARKERS.iter().any(|marker| stderr.contains(marker)) => Self::new(
+                Verdict::MemoryLimitExceeded,
+                signal_of(code),
+                format!("内存超限: {}", stderr),
+            ),
+            // 没有超时却被 SIGKILL 终止，通常是触发了 OOM killer
+            Some(137) => Self::new(
+                Verdict::MemoryLimitExceeded,
+                Some(9),
+                "内存超限（进程被 SIGKILL 终止）".to_string(),
+            ),
+            Some(code) => match signal_of(code) {
+                Some(signal) => Self::new(
+                    Verdict::RuntimeError,
+                    Some(signal),
+                    format!(
+                        "运行错误（信号 {} {}）: {}",
+                        signal,
+                        signal_name(signal),
+                        stderr
+                    ),
+                ),
+                None => Self::new(
+                    Verdict::RuntimeError,
+                    None,
+                    format!("运行错误（退出码 {}）: {}", code, stderr),
+                ),
+            },
+            None => Self::new(Verdict::RuntimeError, None, "无法获取退出码".to_string()),
+        };
+        failure.exit_code = exit_code;
+        Some(failure)
+    }
+
+    /// 进程正常退出却缺少结果，说明用户代码主动结束了进程
+    pub fn early_exit() -> Self {
+        let mut failure = Self::new(Verdict::RuntimeError, None, "程序提前退出".to_string());
+        failure.exit_code = Some(0);
+        failure
+    }
+
+    /// 结果记录重复或不属于本批，说明用户代码冒充测试代码写入了结果
+    pub fn tampered() -> Self {
+        Self::new(Verdict::RuntimeError, None, "结果记录被篡改".to_string())
+    }
+
+    /// 启动器测得的 CPU 时间超过上限
+    pub fn cpu_limit(cpu_ms: f64, limit: Duration) -> Self {
+        Self::new(
+            Verdict::TimeLimitExceeded,
+            None,
+            format!(
+                "CPU 时间超限：{:.0} ms，上限 {} ms",
+                cpu_ms,
+                limit.as_millis()
+            ),
+        )
+    }
+
+    /// 在容器外测得的内存峰值超过上限
+    pub fn memory_limit(peak_memory_kb: i64, limit_mb: u64) -> Self {
+        Self::new(
+            Verdict::MemoryLimitExceeded,
+            None,
+            format!("内存超限：峰值 {} KB，上限 {} MB", peak_memory_kb, limit_mb),
+        )
+    }
+
+    /// 把疑似分配失败引起的崩溃改判为内存超限
+    ///
+    /// 地址空间受 RLIMIT_AS 限制时，超限的分配直接失败，常驻内存峰值不会越过上限。
+    /// 被分配失败常见的信号终止、且峰值已达上限的一半时判为内存超限：
+    /// 按倍增扩容的数组在下一次扩容失败时，峰值大约就在这个位置。
+    pub fn or_allocation_failure(self, peak_memory_kb: i64, limit_mb: u64) -> Self {
+        let near_limit = peak_memory_kb * 2 >= (limit_mb * 1024) as i64;
+        match self.signal {
+            Some(signal)
+                if self.verdict == Verdict::RuntimeError
+                    && near_limit
+                    && ALLOCATION_FAILURE_SIGNALS.contains(&signal) =>
+            {
+                Self {
+                    verdict: Verdict::MemoryLimitExceeded,
+                    message: format!(
+                        "内存超限：进程在峰值 {} KB 时被信号 {} {} 终止，上限 {} MB，推断为内存分配失败",
+                        peak_memory_kb,
+                        signal,
+                        signal_name(signal),
+                        limit_mb
+                    ),
+                    ..self
+                }
+            }
+            _ => self,
+        }
+    }
+
+    /// 标准输出或标准错误超过上限，进程已被终止
+    pub fn output_limit(stream: &str, limit: usize) -> Self {
+        Self::new(
+            Verdict::OutputLimitExceeded,
+            None,
+            format!("{} 超过 {} 字节，已截断", stream, limit),
+        )
+    }
+
+    fn new(verdict: Verdict, signal: Option<i32>, message: String) -> Self {
+        Self {
+            verdict,
+            exit_code: None,
+            signal,
+            message,
+        }
+    }
+}
+
+fn signal_of(exit_code: i64) -> Option<i32> {
+    (129..=192)
+        .contains(&exit_code)
+        .then(|| (exit_code - 128) as i32)
+}
+
+fn signal_name(signal: i32) -> &'static str {
+    match signal {
+        4 => "SIGILL",
+        6 => "SIGABRT",
+        7 => "SIGBUS",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        11 => "SIGSEGV",
+        13 => "SIGPIPE",
+        15 => "SIGTERM",
+        24 => "SIGXCPU",
+        _ => "",
+    }
+}
+
+fn truncate(text: &str, limit: usize) -> &str {
+    match text.char_indices().nth(limit) {
+        Some((index, _)) => &text[..index],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_exit_status() {
+        assert_eq!(RunFailure::from_exit(Some(0), false, ""), None);
+
+        let segfault = RunFailure::from_exit(Some(139), false, "").unwrap();
+        assert_eq!(segfault.verdict, Verdict::RuntimeError);
+        assert_eq!(segfault.signal, Some(11));
+
+        let timeout = RunFailure::from_exit(Some(124), false, "").unwrap();
+        assert_eq!(timeout.verdict, Verdict::TimeLimitExceeded);
+        assert_eq!(
+            RunFailure::from_exit(None, true, "").unwrap().verdict,
+            Verdict::TimeLimitExceeded
+        );
+
+        let oom = RunFailure::from_exit(Some(1), false, "Traceback ...\nMemoryError\n").unwrap();
+        assert_eq!(oom.verdict, Verdict::MemoryLimitExceeded);
+
+        let rust_oom =
+            RunFailure::from_exit(Some(134), false, "memory allocation of 1024 bytes failed\n")
+                .unwrap();
+        assert_eq!(rust_oom.verdict, Verdict::MemoryLimitExceeded);
+
+        let exit = RunFailure::from_exit(Some(3), false, "boom").unwrap();
+        assert_eq!(exit.verdict, Verdict::RuntimeError);
+        assert_eq!(exit.signal, None);
+        assert_eq!(exit.message, "运行错误（退出码 3）: boom");
+    }
+
+    #[test]
+    fn crash_near_memory_limit_is_allocation_failure() {
+        let segfault = || RunFailure::from_exit(Some(139), false, "").unwrap();
+
+        let near = segfault().or_allocation_failure(140 * 1024, 256);
+        assert_eq!(near.verdict, Verdict::MemoryLimitExceeded);
+        assert_eq!(near.signal, Some(11));
+        assert_eq!(near.exit_code, Some(139));
+
+        let far = segfault().or_allocation_failure(1024, 256);
+        assert_eq!(far.verdict, Verdict::RuntimeError);
+
+        let exit = RunFailure::from_exit(Some(1), false, "").unwrap();
+        assert_eq!(
+            exit.or_allocation_failure(250 * 1024, 256).verdict,
+            Verdict::RuntimeError
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use serde_json::{Value, json};
+
+/// 由测试代码记录中的 bench 计算统计量，格式见 [`crate::codegen::RESULTS_FD`]
+///
+/// 每个样本是一批调用的平均耗时（毫秒）；p95 取最近秩，标准差为样本标准差。
+/// 没有样本时返回 None。
+pub fn statistics(bench: &Value) -> Option<Value> {
+    let iterations = bench.get("iterations")?.as_u64()?;
+    let mut samples: Vec<f64> = bench
+        .get("samples")?
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_f64)
+        .collect();
+    if samples.is_empty() {
+        return None;
+    }
+    samples.sort_by(f64::total_cmp);
+
+    let n = samples.len();
+    let mean = samples.iter().sum::<f64>() / n as f64;
+    let median = if n.is_multiple_of(2) {
+        (samples[n / 2 - 1] + samples[n / 2]) / 2.0
+    } else {
+        samples[n / 2]
+    };
+    let p95 = samples[(n * 95).div_ceil(100) - 1];
+    let stddev = if n > 1 {
+        (samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt()
+    } else {
+        0.0
+    };
+
+    Some(json!({
+        "iterations": iterations,
+        "samples": n,
+        "min_ms": samples[0],
+        "median_ms": median,
+        "mean_ms": mean,
+        "p95_ms": p95,
+        "stddev_ms": stddev
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_samples() {
+        let samples: Vec<f64> = (1..=20).rev().map(f64::from).collect();
+        let stats = statistics(&json!({ "iterations": 640, "samples": samples })).unwrap();
+
+        assert_eq!(stats["iterations"], json!(640));
+        assert_eq!(stats["samples"], json!(20));
+        assert_eq!(stats["min_ms"], json!(1.0));
+        assert_eq!(stats["median_ms"], json!(10.5));
+        assert_eq!(stats["mean_ms"], json!(10.5));
+        assert_eq!(stats["p95_ms"], json!(19.0));
+        assert!((stats["stddev_ms"].as_f64().unwrap() - 5.9161).abs() < 1e-4);
+
+        assert!(statistics(&json!({ "iterations": 0, "samples": [] })).is_none());
+    }
+}
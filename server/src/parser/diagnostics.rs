@@ -0,0 +1,134 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+/// gcc / clang / javac / go 风格：`file:line[:column]: [severity:] message`
+static RE_LOCATED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(?P<file>[^:\s][^:]*):(?P<line>\d+):(?:(?P<column>\d+):)?\s*(?:(?P<severity>fatal error|error|warning|note):\s*)?(?P<message>.+)$",
+    )
+    .unwrap()
+});
+
+/// rustc 风格的首行：`error[E0308]: message`
+static RE_RUST_HEADER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?P<severity>error|warning|note)(?:\[\w+\])?: (?P<message>.+)$").unwrap()
+});
+
+/// rustc 风格的位置行：`  --> file:line:column`
+static RE_RUST_LOCATION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*--> (?P<file>[^:]+):(?P<line>\d+):(?P<column>\d+)$").unwrap());
+
+/// 编译器输出中的一条诊断
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub file: String,
+    /// 用户代码中的行号，从 1 开始；in_user_code 为 false 时为生成文件中的行号
+    pub line: usize,
+    pub column: Option<usize>,
+    /// error / warning / note
+    pub severity: String,
+    pub message: String,
+    /// 诊断是否落在用户代码范围内
+    pub in_user_code: bool,
+}
+
+/// 从编译器输出中提取诊断，无法识别的行忽略
+pub fn parse_diagnostics(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut rust_header: Option<(String, String)> = None;
+
+    for line in output.lines() {
+        if let Some(cap) = RE_RUST_LOCATION.captures(line) {
+            if let Some((severity, message)) = rust_header.take() {
+                diagnostics.push(Diagnostic {
+                    file: file_name(&cap["file"]),
+                    line: cap["line"].parse().unwrap_or(0),
+                    column: cap["column"].parse().ok(),
+                    severity,
+                    message,
+                    in_user_code: true,
+                });
+            }
+        } else if let Some(cap) = RE_RUST_HEADER.captures(line) {
+            rust_header = Some((cap["severity"].to_string(), cap["message"].to_string()));
+        } else if let Some(cap) = RE_LOCATED.captures(line) {
+            let severity = match cap.name("severity").map(|m| m.as_str()) {
+                Some("fatal error") | None => "error",
+                Some(severity) => severity,
+            };
+            diagnostics.push(Diagnostic {
+                file: file_name(&cap["file"]),
+                line: cap["line"].parse().unwrap_or(0),
+                column: cap.name("column").and_then(|m| m.as_str().parse().ok()),
+                severity: severity.to_string(),
+                message: cap["message"].trim().to_string(),
+                in_user_code: true,
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// 把生成文件中的行号换算为用户代码中的行号
+///
+/// 用户代码原样嵌入生成文件，按其在生成文件中的起始行平移；
+/// 找不到用户代码（如 Go 拆分了 import）时不做换算。
+pub fn relocate(diagnostics: &mut [Diagnostic], generated: &str, code: &str) {
+    let Some(offset) = generated.find(code) else {
+        return;
+    };
+    let first = generated[..offset].matches('\n').count() + 1;
+    let last = first + code.lines().count().max(1) - 1;
+
+    for diagnostic in diagnostics {
+        if (first..=last).contains(&diagnostic.line) {
+            diagnostic.line -= first - 1;
+        } else {
+            diagnostic.in_user_code = false;
+        }
+    }
+}
+
+fn file_name(path: &str) -> String {
+    path.rsplit('/').next().unwrap_or(path).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_gcc_and_rustc_diagnostics() {
+        let output = "/tmp/main_1.c: In function 'add':\n\
+                      /tmp/main_1.c:9:12: error: 'c' undeclared (first use in this function)\n\
+                      /tmp/main_1.c:20:5: warning: unused variable 'x' [-Wunused-variable]\n\
+                      error[E0308]: mismatched types\n \
+                      --> /tmp/main_2.rs:3:5\n";
+
+        let mut diagnostics = parse_diagnostics(output);
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0].file, "main_1.c");
+        assert_eq!(diagnostics[0].severity, "error");
+        assert_eq!(diagnostics[0].column, Some(12));
+        assert_eq!(diagnostics[1].severity, "warning");
+        assert_eq!(diagnostics[2].message, "mismatched types");
+        assert_eq!(diagnostics[2].line, 3);
+
+        // 用户代码从生成文件第 8 行开始，共 3 行
+        let generated = format!(
+            "{}int add() {{\n  return c;\n}}\n{}",
+            "#\n".repeat(7),
+            "x\n".repeat(20)
+        );
+        relocate(
+            &mut diagnostics[..2],
+            &generated,
+            "int add() {\n  return c;\n}",
+        );
+        assert_eq!(diagnostics[0].line, 2);
+        assert!(!diagnostics[1].in_user_code);
+        assert_eq!(diagnostics[1].line, 20);
+    }
+}
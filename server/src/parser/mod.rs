@@ -1 +1,3 @@
+pub mod benchmark;
+pub mod diagnostics;
 pub mod parse_output;
@@ -1,53 +1,141 @@
-use once_cell::sync::Lazy;
-use regex::Regex;
 use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::ops::Range;
 
-/// 解析每行测试用例输出为统一 JSON
-pub fn parse_output_to_json(output: &str) -> Value {
-    static RE_OUTPUT: Lazy<Regex> = Lazy::new(|| {
-        Regex::new(
-            r"Input: \((?P<input>.*?)\) => Output: (?P<output>-?\d+) \| Expected: (?P<expected>-?\d+) \| (?P<result>✅ PASS|❌ FAIL) \| Time: (?P<time>[0-9.]+)ms"
-        ).unwrap()
-    });
+use crate::codegen::HarnessSpec;
+use crate::model::verdict::{RunFailure, Verdict};
+use crate::parser::benchmark::statistics;
 
-    let mut cases = Vec::new();
+/// 由各批次收集到的记录（见 [`collect_records`]）生成每个用例的结果
+///
+/// 输入与期望值取自请求本身。缺少记录的用例（如程序中途崩溃）视为未通过，
+/// 在 failures 中的用例使用对应的进程异常原因作为判定。
+/// 内存峰值由执行器在进程外测得，不采信测试代码自己的汇报。
+pub fn parse_records(
+    mut outputs: HashMap<usize, Value>,
+    spec: &HarnessSpec,
+    failures: &HashMap<usize, RunFailure>,
+    peak_memory_kb: i64,
+) -> Value {
+    // 一条有效记录都没有，通常是编译失败或运行前崩溃
+    if outputs.is_empty() && failures.is_empty() {
+        return summarize(Vec::new(), 0.0, 0);
+    }
+
+    let mut cases = Vec::with_capacity(spec.test_cases.len());
     let mut total_time = 0.0;
-    let mut peak_memory_kb = 0i64;
 
-    for line in output.lines() {
-        if let Some(cap) = RE_OUTPUT.captures(line) {
-            let input_str = &cap["input"];
-            let output_val: i64 = cap["output"].parse().unwrap_or(0);
-            let expected_val: i64 = cap["expected"].parse().unwrap_or(0);
-            let passed = &cap["result"] == "✅ PASS";
-            let time_ms: f64 = cap["time"].parse().unwrap_or(0.0);
+    for (index, t) in spec.test_cases.iter().enumerate() {
+        let expected = spec.signature.returns.canonical(&t.expected);
+        let case = match (outputs.remove(&index), failures.get(&index)) {
+            (Some(record), _) => {
+                let time_ms = record.get("time_ms").and_then(Value::as_f64).unwrap_or(0.0);
+                let passed = record.get("passed").and_then(Value::as_bool) == Some(true);
+                total_time += time_ms;
+                let mut case = json!({
+                    "input": t.input,
+                    "output": record.get("output").cloned().unwrap_or(Value::Null),
+                    "expected": expected,
+                    "passed": passed,
+                    "verdict": Verdict::from_passed(passed),
+                    "comparison": spec.comparison,
+                    "time_ms": time_ms
+                });
+                if let Some(benchmark) = record.get("bench").and_then(statistics) {
+                    case["benchmark"] = benchmark;
+                }
+                case
+            }
+            (None, Some(failure)) => {
+                let mut case = json!({
+                    "input": t.input,
+                    "output": null,
+                    "expected": expected,
+                    "passed": false,
+                    "verdict": failure.verdict,
+                    "comparison": spec.comparison,
+                    "time_ms": 0.0,
+                    "message": failure.message
+                });
+                if let Some(exit_code) = failure.exit_code {
+                    case["exit_code"] = json!(exit_code);
+                }
+                if let Some(signal) = failure.signal {
+                    case["signal"] = json!(signal);
+                }
+                case
+            }
+            (None, None) => json!({
+                "input": t.input,
+                "output": null,
+                "expected": expected,
+                "passed": false,
+                "verdict": Verdict::RuntimeError,
+                "comparison": spec.comparison,
+                "time_ms": 0.0,
+                "message": "未产生结果，程序可能已崩溃或超时"
+            }),
+        };
+        cases.push(case);
+    }
 
-            total_time += time_ms;
+    summarize(cases, total_time, peak_memory_kb)
+}
 
-            let input_vec: Vec<i64> = if input_str.trim().is_empty() {
-                vec![]
-            } else {
-                input_str
-                    .split(',')
-                    .map(|s| s.trim().parse().unwrap_or(0))
-                    .collect()
-            };
+/// 整次提交的判定：第一个未通过用例的判定，全部通过时为 accepted
+pub fn overall_verdict(output: &Value) -> Verdict {
+    let Some(cases) = output.get("cases").and_then(Value::as_array) else {
+        return Verdict::RuntimeError;
+    };
+
+    cases
+        .iter()
+        .filter_map(|case| serde_json::from_value(case.get("verdict")?.clone()).ok())
+        .find(|verdict| *verdict != Verdict::Accepted)
+        .unwrap_or(Verdict::Accepted)
+}
 
-            cases.push(json!({
-                "input": input_vec,
-                "output": output_val,
-                "expected": expected_val,
-                "passed": passed,
-                "time_ms": time_ms
-            }));
-        } else if line.starts_with("Memory:") {
-            // 提取峰值内存
-            if let Some(val) = line.split(':').nth(1) {
-                peak_memory_kb = val.trim().trim_end_matches(" KB").parse().unwrap_or(0);
+/// 一批运行写下的、带有本次 nonce 的记录
+#[derive(Debug, Default)]
+pub struct Records {
+    /// 按用例下标的记录
+    pub cases: HashMap<usize, Value>,
+    /// 同一用例或结束标记出现多次，或记录了本批没有运行的用例
+    pub tampered: bool,
+}
+
+/// 解析一批运行写入结果通道的 JSON 行，格式见 [`crate::codegen::RESULTS_FD`]
+///
+/// 只采信带有本次 nonce 的记录。测试代码对 range 内的每个用例只写一条记录，
+/// 多出来的记录只可能是用户代码冒充的，此时整批结果都不可信。
+pub fn collect_records(records: &str, nonce: &str, range: Range<usize>) -> Records {
+    let mut collected = Records::default();
+    let mut done = false;
+
+    for line in records.lines() {
+        let Ok(record) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if record.get("nonce").and_then(Value::as_str) != Some(nonce) {
+            continue;
+        }
+
+        if let Some(index) = record.get("index").and_then(Value::as_u64) {
+            let index = index as usize;
+            if !range.contains(&index) || collected.cases.insert(index, record).is_some() {
+                collected.tampered = true;
             }
+        } else if record.get("done").is_some() {
+            collected.tampered |= done;
+            done = true;
         }
     }
 
+    collected
+}
+
+/// 由各用例结果生成统一的 summary / cases JSON
+pub fn summarize(cases: Vec<Value>, total_time: f64, peak_memory_kb: i64) -> Value {
     let total = cases.len();
     let pass_count = cases
         .iter()
@@ -69,37 +157,206 @@ pub fn parse_output_to_json(output: &str) -> Value {
                 "pass_rate": pass_rate,
                 "total_time_ms": total_time,
                 "peak_memory_kb": peak_memory_kb,
-                "time_complexity": estimate_time_complexity(total_time),
-                "space_complexity": estimate_space_complexity(peak_memory_kb as f64)
+                // 已弃用：按总耗时与内存峰值分档的估计没有依据，保留字段以兼容旧客户端，
+                // 复杂度改由请求中的 complexity 拟合
+                "time_complexity": null,
+                "space_complexity": null
             },
             "cases": cases
         })
     }
 }
 
-// 分别估算时间和空间复杂度
-fn estimate_time_complexity(ms: f64) -> &'static str {
-    if ms < 1.0 {
-        "O(1)"
-    } else if ms < 10.0 {
-        "O(log n)"
-    } else if ms < 100.0 {
-        "O(n)"
-    } else if ms < 1000.0 {
-        "O(n log n)"
-    } else {
-        "O(n²)"
+/// checker 对单个用例的判定
+pub struct CheckerVerdict {
+    pub passed: bool,
+    pub message: String,
+}
+
+/// 解析 checker 输出中以 PASS / FAIL 开头的行，其余行忽略
+pub fn parse_checker_output(output: &str) -> Vec<CheckerVerdict> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (passed, rest) = if let Some(rest) = line.strip_prefix("PASS") {
+                (true, rest)
+            } else {
+                (false, line.strip_prefix("FAIL")?)
+            };
+            // PASSED 之类的前缀不算判定
+            if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+                return None;
+            }
+            Some(CheckerVerdict {
+                passed,
+                message: rest.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// 用 checker 的判定覆盖每个用例的 passed，并重新统计通过数
+///
+/// checker 给出的判定少于用例数时，缺少判定的用例视为未通过；
+/// 运行错误、超时等进程异常的用例保持原判定。
+pub fn apply_checker_verdicts(
+    result: &mut Value,
+    verdicts: &[CheckerVerdict],
+    checker_language: &str,
+) {
+    let Some(cases) = result.get_mut("cases").and_then(Value::as_array_mut) else {
+        return;
+    };
+
+    let judged = [json!(Verdict::Accepted), json!(Verdict::WrongAnswer)];
+    for (index, case) in cases.iter_mut().enumerate() {
+        if case.get("verdict").is_some_and(|v| !judged.contains(v)) {
+            continue;
+        }
+        let (passed, message) = match verdicts.get(index) {
+            Some(verdict) => (verdict.passed, verdict.message.as_str()),
+            None => (false, "checker 未给出判定"),
+        };
+        case["passed"] = json!(passed);
+        case["verdict"] = json!(Verdict::from_passed(passed));
+        case["message"] = json!(message);
+        case["comparison"] = json!({"mode": "checker", "language": checker_language});
+    }
+
+    let total = cases.len();
+    let pass_count = cases
+        .iter()
+        .filter(|c| c.get("passed").and_then(|v| v.as_bool()) == Some(true))
+        .count();
+    if let Some(summary) = result.get_mut("summary") {
+        summary["passed"] = json!(pass_count);
+        summary["pass_rate"] = json!(pass_count as f64 / total as f64);
     }
 }
 
-fn estimate_space_complexity(kb: f64) -> &'static str {
-    if kb < 256.0 {
-        "O(1)"
-    } else if kb < 1024.0 {
-        "O(n)"
-    } else if kb < 10240.0 {
-        "O(nlog n)"
-    } else {
-        "O(n²)"
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::model::comparison::Comparison;
+    use crate::model::request::TestCase;
+    use crate::model::signature::Signature;
+
+    const NONCE: &str = "3f2a";
+
+    fn parse(records: &str) -> Value {
+        let signature = Signature::all_int(2);
+        let test_cases = vec![
+            TestCase {
+                input: vec![json!(1), json!(2)],
+                expected: json!(4),
+                size: None,
+            },
+            TestCase {
+                input: vec![json!(2), json!(2)],
+                expected: json!(4),
+                size: None,
+            },
+        ];
+        let failures = HashMap::from([(1, RunFailure::from_exit(Some(139), false, "").unwrap())]);
+        let records = collect_records(records, NONCE, 0..2);
+        assert!(!records.tampered);
+        parse_records(
+            records.cases,
+            &HarnessSpec {
+                code: "",
+                function: "add",
+                signature: &signature,
+                comparison: &Comparison::Exact,
+                test_cases: &test_cases,
+                benchmark: None,
+            },
+            &failures,
+            512,
+        )
+    }
+
+    #[test]
+    fn ignores_records_without_nonce() {
+        let result = parse(
+            "{\"nonce\":\"forged\",\"index\":0,\"passed\":true,\"time_ms\":0.1,\"output\":4}\n\
+             {\"nonce\":\"3f2a\",\"index\":0,\"passed\":false,\"time_ms\":0.1,\"output\":3}\n\
+             Output: 4 | ✅ PASS\n\
+             {\"nonce\":\"3f2a\",\"done\":true,\"time_ms\":0.2}\n",
+        );
+
+        assert_eq!(result["cases"][0]["passed"], json!(false));
+        assert_eq!(result["cases"][0]["output"], json!(3));
+        assert_eq!(result["cases"][0]["verdict"], json!("wrong_answer"));
+        // 第二个用例没有记录，使用进程异常原因
+        assert_eq!(result["cases"][1]["passed"], json!(false));
+        assert_eq!(result["cases"][1]["output"], Value::Null);
+        assert_eq!(result["cases"][1]["verdict"], json!("runtime_error"));
+        assert_eq!(result["cases"][1]["signal"], json!(11));
+        assert_eq!(overall_verdict(&result), Verdict::WrongAnswer);
+        assert_eq!(result["summary"]["passed"], json!(0));
+        assert_eq!(result["summary"]["peak_memory_kb"], json!(512));
+        assert_eq!(result["summary"]["time_complexity"], Value::Null);
+        assert!(result["summary"].get("space_complexity").is_some());
+    }
+
+    #[test]
+    fn rejects_duplicate_and_unsolicited_records() {
+        let record = |index: usize, passed: bool| {
+            format!(
+                "{{\"nonce\":\"3f2a\",\"index\":{},\"passed\":{},\"time_ms\":0.1,\"output\":4}}\n",
+                index, passed
+            )
+        };
+        let done = "{\"nonce\":\"3f2a\",\"done\":true,\"time_ms\":0.2}\n";
+
+        let honest = collect_records(&format!("{}{}", record(0, false), done), NONCE, 0..1);
+        assert!(!honest.tampered);
+        assert_eq!(honest.cases[&0]["passed"], json!(false));
+
+        // 用户代码抢先写入的记录与测试代码自己的记录重复
+        let duplicate = format!("{}{}{}", record(0, true), record(0, false), done);
+        assert!(collect_records(&duplicate, NONCE, 0..1).tampered);
+
+        // 本批只运行用例 0，却出现了用例 1 的记录
+        let unsolicited = format!("{}{}{}", record(0, false), record(1, true), done);
+        assert!(collect_records(&unsolicited, NONCE, 0..1).tampered);
+
+        let twice_done = format!("{}{}{}", record(0, false), done, done);
+        assert!(collect_records(&twice_done, NONCE, 0..1).tampered);
+    }
+
+    #[test]
+    fn parses_checker_verdicts() {
+        let verdicts = parse_checker_output("debug\nPASS any order is fine\nPASSED\nFAIL\n");
+
+        assert_eq!(verdicts.len(), 2);
+        assert!(verdicts[0].passed);
+        assert_eq!(verdicts[0].message, "any order is fine");
+        assert!(!verdicts[1].passed);
+        assert_eq!(verdicts[1].message, "");
+    }
+
+    #[test]
+    fn checker_overrides_builtin_verdict() {
+        let mut result = parse(
+            "{\"nonce\":\"3f2a\",\"index\":0,\"passed\":false,\"time_ms\":0.1,\"output\":3}\n\
+             {\"nonce\":\"3f2a\",\"index\":1,\"passed\":true,\"time_ms\":0.1,\"output\":4}\n",
+        );
+        let verdicts = parse_checker_output("PASS ok\n");
+
+        apply_checker_verdicts(&mut result, &verdicts, "python");
+
+        assert_eq!(result["cases"][0]["passed"], json!(true));
+        assert_eq!(result["cases"][0]["message"], json!("ok"));
+        assert_eq!(
+            result["cases"][0]["comparison"],
+            json!({"mode": "checker", "language": "python"})
+        );
+        // 第二个用例缺少判定
+        assert_eq!(result["cases"][1]["passed"], json!(false));
+        assert_eq!(result["summary"]["passed"], json!(1));
+        assert_eq!(result["summary"]["pass_rate"], json!(0.5));
     }
 }
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use crate::codegen::template_generator::TemplateCodeGenerator;
+use crate::codegen::{CodeGenerator, generator_for};
+use crate::config::{Backend, Config, LanguageConfig};
+use crate::docker::sandbox::Target;
+
+/// 单个已启用语言的全部运行信息
+pub struct LanguageEntry {
+    pub generator: Box<dyn CodeGenerator>,
+    pub target: Target,
+    pub config: LanguageConfig,
+}
+
+/// 启动时根据配置构建的语言注册表，是请求处理时唯一的语言来源
+pub struct LanguageRegistry {
+    languages: HashMap<String, LanguageEntry>,
+}
+
+impl LanguageRegistry {
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let mut languages = HashMap::new();
+
+        for (id, lang_config) in config.languages.iter().filter(|(_, cfg)| cfg.enabled) {
+            lang_config
+                .check_limits(&config.server)
+                .map_err(|e| anyhow::anyhow!("语言 '{}' 的配置无效: {}", id, e))?;
+            // 配置了模板的语言使用模板生成器，无需重新编译服务端
+            let generator = match &lang_config.template {
+                Some(template) => Box::new(
+                    TemplateCodeGenerator::new(template.clone())
+                        .map_err(|e| anyhow::anyhow!("语言 '{}' 的模板无效: {}", id, e))?,
+                ),
+                None => generator_for(id).ok_or_else(|| {
+                    anyhow::anyhow!("语言 '{}' 已启用，但没有对应的代码生成器", id)
+                })?,
+            };
+            let target = match lang_config.backend {
+                Backend::Shared => Target::Shared {
+                    container: config
+                        .get_container(id)
+                        .ok_or_else(|| anyhow::anyhow!("语言 '{}' 已启用，但未配置容器", id))?
+                        .to_string(),
+                },
+                Backend::Ephemeral => Target::Ephemeral {
+                    image: config
+                        .get_image(id)
+                        .ok_or_else(|| anyhow::anyhow!("语言 '{}' 使用临时容器，但未配置镜像", id))?
+                        .to_string(),
+                },
+            };
+
+            languages.insert(
+                id.clone(),
+                LanguageEntry {
+                    generator,
+                    target,
+                    config: lang_config.clone(),
+                },
+            );
+        }
+
+        Ok(Self { languages })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&LanguageEntry> {
+        self.languages.get(id)
+    }
+
+    /// 按语言 id 排序返回所有已注册语言
+    pub fn iter(&self) -> Vec<(&str, &LanguageEntry)> {
+        let mut entries: Vec<_> = self
+            .languages
+            .iter()
+            .map(|(id, entry)| (id.as_str(), entry))
+            .collect();
+        entries.sort_by_key(|(id, _)| *id);
+        entries
+    }
+}